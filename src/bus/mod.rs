@@ -1,4 +1,6 @@
 
+use std::ops::Range;
+
 
 pub trait BitSize {
     fn from(values: &[u8]) -> Self;
@@ -32,3 +34,128 @@ pub trait DataBus {
 }
 
 
+// a memory mapped device model, offsets are relative to the address it is attached at
+pub trait Peripheral: PeripheralClone {
+    fn read(&mut self, offset: usize, size: usize) -> u32;
+
+    fn write(&mut self, offset: usize, size: usize, value: u32);
+
+    // advances the device by the given number of processor cycles
+    fn tick(&mut self, _cycles: u64) {}
+
+    // the level of the interrupt line, sampled after every step
+    fn interrupt(&self) -> bool { false }
+}
+
+pub trait PeripheralClone {
+    fn clone_box(&self) -> Box<dyn Peripheral>;
+}
+
+impl<T> PeripheralClone for T where T: Peripheral + Clone + 'static {
+    fn clone_box(&self) -> Box<dyn Peripheral> { Box::new(self.clone()) }
+}
+
+impl Clone for Box<dyn Peripheral> {
+    fn clone(&self) -> Box<dyn Peripheral> { self.clone_box() }
+}
+
+#[derive(Clone)]
+struct Region {
+    range: Range<usize>,
+    irq: Option<usize>,
+    peripheral: Box<dyn Peripheral>,
+}
+
+// the devices attached to the system bus outside of the processor core
+#[derive(Clone)]
+pub struct Bus {
+    regions: Vec<Region>,
+}
+
+impl Bus {
+    pub(crate) fn new() -> Bus {
+        Bus {
+            regions: Vec::new(),
+        }
+    }
+
+    pub fn attach(&mut self, range: Range<usize>, irq: Option<usize>, peripheral: Box<dyn Peripheral>) {
+        self.regions.push(Region {
+            range,
+            irq,
+            peripheral,
+        });
+    }
+
+    fn region(&mut self, addr: usize, size: usize) -> Option<&mut Region> {
+        self.regions.iter_mut().find(|region| region.range.contains(&addr) && addr + size <= region.range.end)
+    }
+
+    // none when no device decodes the address
+    pub fn read(&mut self, addr: usize, size: usize) -> Option<u32> {
+        self.region(addr, size).map(|region| region.peripheral.read(addr - region.range.start, size))
+    }
+
+    pub fn write(&mut self, addr: usize, size: usize, value: u32) -> bool {
+        self.region(addr, size).map(|region| region.peripheral.write(addr - region.range.start, size, value)).is_some()
+    }
+
+    // returns the interrupts whose lines are asserted after the devices advanced
+    pub fn tick(&mut self, cycles: u64) -> Vec<usize> {
+        self.regions.iter_mut()
+            .filter_map(|region| {
+                region.peripheral.tick(cycles);
+
+                region.irq.filter(|_| region.peripheral.interrupt())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Latch {
+        value: u32,
+    }
+
+    impl Peripheral for Latch {
+        fn read(&mut self, offset: usize, size: usize) -> u32 {
+            (self.value >> (offset * 8)) & (u32::MAX >> (32 - size * 8))
+        }
+
+        fn write(&mut self, _offset: usize, _size: usize, value: u32) {
+            self.value = value;
+        }
+
+        fn interrupt(&self) -> bool {
+            self.value != 0
+        }
+    }
+
+    #[test]
+    fn bus() {
+        let mut bus = Bus::new();
+
+        bus.attach(0x40000000..0x40000004, Some(3), Box::new(Latch { value: 0 }));
+
+        assert_eq!(bus.tick(1), Vec::<usize>::new());
+
+        assert!(bus.write(0x40000000, 4, 0x12345678));
+        assert!(!bus.write(0x40000004, 4, 0));
+        assert!(!bus.write(0x40000002, 4, 0));
+
+        assert_eq!(bus.read(0x40000000, 4), Some(0x12345678));
+        assert_eq!(bus.read(0x40000002, 2), Some(0x1234));
+        assert_eq!(bus.read(0x3ffffffc, 4), None);
+
+        let mut clone = bus.clone();
+
+        clone.write(0x40000000, 4, 0);
+
+        assert_eq!(bus.tick(1), vec![3]);
+        assert_eq!(clone.tick(1), Vec::<usize>::new());
+    }
+}
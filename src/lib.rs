@@ -0,0 +1,4 @@
+pub mod processor;
+pub mod memory;
+pub mod bus;
+pub mod machine;
@@ -0,0 +1,367 @@
+use crate::bus::Peripheral;
+use crate::memory::Permissions;
+use crate::processor::IRQ_COUNT;
+
+use serde::Deserialize;
+
+use std::path::{Path, PathBuf};
+use std::fs;
+
+// accesses here are redirected to single bits of the sram and peripheral regions
+const BIT_BAND: [(u64, u64); 2] = [(0x22000000, 0x24000000), (0x42000000, 0x44000000)];
+
+#[derive(Debug)]
+pub enum Error {
+    Overlap(String, String),
+    Size(String),
+    Reserved(String),
+    Irq(String),
+    Target(String),
+    BitBand(String, u32),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Error::Overlap(a, b) => f.write_fmt(format_args!("{} overlaps {}", a, b)),
+            Error::Size(name) => f.write_fmt(format_args!("backing file of {} is larger than the region", name)),
+            Error::Reserved(name) => f.write_fmt(format_args!("{} reaches into the system region at 0xe0000000", name)),
+            Error::Irq(name) => f.write_fmt(format_args!("{} drives an interrupt above {}", name, IRQ_COUNT - 1)),
+            Error::Target(name) => f.write_fmt(format_args!("{} does not alias a single memory region", name)),
+            Error::BitBand(name, base) => f.write_fmt(format_args!("{} overlaps the bit-band alias region at {:#x}", name, base)),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryRegion {
+    pub name: String,
+    pub base: u32,
+    pub size: u32,
+    pub permissions: Permissions,
+    pub file: Option<PathBuf>,
+    #[serde(skip)]
+    pub contents: Vec<u8>,
+}
+
+// a second view of memory that is already mapped at the target address
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Alias {
+    pub name: String,
+    pub base: u32,
+    pub size: u32,
+    pub target: u32,
+}
+
+impl Alias {
+    pub fn translate(&self, addr: usize) -> Option<usize> {
+        (addr >= self.base as usize && addr < self.base as usize + self.size as usize).then(|| addr - self.base as usize + self.target as usize)
+    }
+}
+
+// stub is the only model so far, anything else is rejected when the description is parsed
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Stub,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PeripheralInstance {
+    pub name: String,
+    pub kind: Kind,
+    pub base: u32,
+    pub size: u32,
+    pub irq: Option<usize>,
+}
+
+impl PeripheralInstance {
+    pub fn build(&self) -> Box<dyn Peripheral> {
+        match self.kind {
+            Kind::Stub => Box::new(Stub::new(self.size as usize)),
+        }
+    }
+}
+
+// the memory map of a chip, loaded from a toml description
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Machine {
+    #[serde(default)]
+    pub vtor: u32,
+    #[serde(default)]
+    pub memory: Vec<MemoryRegion>,
+    #[serde(default)]
+    pub alias: Vec<Alias>,
+    #[serde(default)]
+    pub peripheral: Vec<PeripheralInstance>,
+}
+
+impl Default for Machine {
+    fn default() -> Machine {
+        Machine::nrf52833()
+    }
+}
+
+impl Machine {
+    // a flat map of writable flash and ram that the processor tests load their code into
+    #[cfg(test)]
+    pub(crate) fn flat() -> Machine {
+        let region = |name: &str, base: u32, size: u32| MemoryRegion {
+            name: String::from(name),
+            base,
+            size,
+            permissions: Permissions::all(),
+            file: None,
+            contents: Vec::new(),
+        };
+
+        Machine {
+            vtor: 0,
+            memory: vec![region("flash", 0x0, 0x10004), region("ram", 0x20000000, 0x3ffc)],
+            alias: Vec::new(),
+            peripheral: Vec::new(),
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Machine> {
+        match name {
+            "nrf52833" => Some(Machine::nrf52833()),
+            _ => None,
+        }
+    }
+
+    // the memory map of the nrf52833 with its code ram alias and information registers
+    pub fn nrf52833() -> Machine {
+        let region = |name: &str, base: u32, size: u32, permissions: &str, contents: Vec<u8>| MemoryRegion {
+            name: String::from(name),
+            base,
+            size,
+            permissions: Permissions::try_from(String::from(permissions)).expect("valid permissions"),
+            file: None,
+            contents,
+        };
+
+        // unprogrammed information words read as all ones
+        let mut ficr = vec![0xff; 0x1000];
+
+        for (offset, value) in [(0x010, 0x1000), (0x014, 0x80), (0x100, 0x52833), (0x10c, 0x80), (0x110, 0x200)] {
+            ficr[offset..offset + 4].copy_from_slice(&u32::to_le_bytes(value));
+        }
+
+        Machine {
+            vtor: 0,
+            memory: vec![
+                region("flash", 0x0, 0x80000, "r-x", Vec::new()),
+                region("ficr", 0x10000000, 0x1000, "r--", ficr),
+                region("uicr", 0x10001000, 0x1000, "r--", vec![0xff; 0x1000]),
+                region("ram", 0x20000000, 0x20000, "rwx", Vec::new()),
+            ],
+            alias: vec![Alias {
+                name: String::from("code ram"),
+                base: 0x00800000,
+                size: 0x20000,
+                target: 0x20000000,
+            }],
+            peripheral: Vec::new(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Machine, Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+
+        Machine::parse(&fs::read_to_string(path)?, path.parent().unwrap_or(Path::new(".")))
+    }
+
+    // backing files are relative to the given directory
+    pub fn parse(description: &str, directory: &Path) -> Result<Machine, Box<dyn std::error::Error>> {
+        let mut machine: Machine = toml::from_str(description)?;
+
+        for region in machine.memory.iter_mut() {
+            if let Some(file) = &region.file {
+                region.contents = fs::read(directory.join(file))?;
+            }
+        }
+
+        machine.validate()?;
+
+        Ok(machine)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        let ranges = self.memory.iter()
+            .map(|region| (&region.name, region.base as u64, region.base as u64 + region.size as u64))
+            .chain(self.alias.iter().map(|alias| (&alias.name, alias.base as u64, alias.base as u64 + alias.size as u64)))
+            .chain(self.peripheral.iter().map(|peripheral| (&peripheral.name, peripheral.base as u64, peripheral.base as u64 + peripheral.size as u64)))
+            .collect::<Vec<(&String, u64, u64)>>();
+
+        for (index, (name, base, end)) in ranges.iter().enumerate() {
+            if *end > 0xe0000000 {
+                return Err(Error::Reserved(name.to_string()));
+            }
+
+            if let Some((alias, _)) = BIT_BAND.iter().find(|(alias, alias_end)| base < alias_end && alias < end) {
+                return Err(Error::BitBand(name.to_string(), *alias as u32));
+            }
+
+            if let Some((other, _, _)) = ranges[index + 1..].iter().find(|(_, other_base, other_end)| base < other_end && other_base < end) {
+                return Err(Error::Overlap(name.to_string(), other.to_string()));
+            }
+        }
+
+        if let Some(region) = self.memory.iter().find(|region| region.contents.len() > region.size as usize) {
+            return Err(Error::Size(region.name.clone()));
+        }
+
+        let target = |alias: &&Alias| !self.memory.iter().any(|region| alias.target >= region.base && alias.target as u64 + alias.size as u64 <= region.base as u64 + region.size as u64);
+
+        if let Some(alias) = self.alias.iter().find(target) {
+            return Err(Error::Target(alias.name.clone()));
+        }
+
+        if let Some(peripheral) = self.peripheral.iter().find(|peripheral| peripheral.irq.is_some_and(|irq| irq >= IRQ_COUNT)) {
+            return Err(Error::Irq(peripheral.name.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+// reads back whatever was last written, standing in for devices that have no model yet
+#[derive(Clone)]
+pub struct Stub {
+    data: Vec<u8>,
+}
+
+impl Stub {
+    pub fn new(size: usize) -> Stub {
+        Stub {
+            data: vec![0; size],
+        }
+    }
+}
+
+impl Peripheral for Stub {
+    fn read(&mut self, offset: usize, size: usize) -> u32 {
+        self.data[offset..offset + size].iter().rev().fold(0, |value, byte| (value << 8) | *byte as u32)
+    }
+
+    fn write(&mut self, offset: usize, size: usize, value: u32) {
+        self.data[offset..offset + size].copy_from_slice(&value.to_le_bytes()[..size]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn machine() {
+        let machine = Machine::parse(r#"
+            vtor = 0x1000
+
+            [[memory]]
+            name = "flash"
+            base = 0x0
+            size = 0x80000
+            permissions = "r-x"
+
+            [[memory]]
+            name = "ram"
+            base = 0x20000000
+            size = 0x20000
+            permissions = "rw-"
+
+            [[peripheral]]
+            name = "uart0"
+            kind = "stub"
+            base = 0x40002000
+            size = 0x1000
+            irq = 2
+        "#, Path::new(".")).unwrap();
+
+        assert_eq!(machine.vtor, 0x1000);
+        assert_eq!(machine.memory[1].size, 0x20000);
+        assert_eq!(machine.memory[0].permissions, Permissions { read: true, write: false, execute: true });
+        assert_eq!(machine.peripheral[0].irq, Some(2));
+    }
+
+    #[test]
+    fn nrf52833() {
+        let machine = Machine::builtin("nrf52833").unwrap();
+
+        assert!(machine.validate().is_ok());
+        assert_eq!(machine.alias[0].translate(0x00800010), Some(0x20000010));
+        assert_eq!(machine.alias[0].translate(0x00820000), None);
+        assert_eq!(&machine.memory[1].contents[0x100..0x104], &[0x33, 0x28, 0x05, 0x00]);
+        assert_eq!(Machine::default().memory[0].permissions, machine.memory[0].permissions);
+        assert_eq!(Machine::default().memory[3].size, 0x20000);
+    }
+
+    #[test]
+    fn invalid_machine() {
+        let overlap = Machine::parse(r#"
+            [[memory]]
+            name = "flash"
+            base = 0x0
+            size = 0x1000
+            permissions = "r-x"
+
+            [[peripheral]]
+            name = "uart0"
+            kind = "stub"
+            base = 0x800
+            size = 0x1000
+        "#, Path::new("."));
+
+        assert_eq!(overlap.unwrap_err().to_string(), "flash overlaps uart0");
+
+        let reserved = Machine::parse(r#"
+            [[memory]]
+            name = "ram"
+            base = 0xdffff000
+            size = 0x2000
+            permissions = "rw-"
+        "#, Path::new("."));
+
+        assert!(reserved.is_err());
+
+        let alias = Machine::parse(r#"
+            [[alias]]
+            name = "code ram"
+            base = 0x00800000
+            size = 0x1000
+            target = 0x20000000
+        "#, Path::new("."));
+
+        assert_eq!(alias.unwrap_err().to_string(), "code ram does not alias a single memory region");
+
+        let bitband = Machine::parse(r#"
+            [[memory]]
+            name = "ram"
+            base = 0x21000000
+            size = 0x2000000
+            permissions = "rw-"
+        "#, Path::new("."));
+
+        assert_eq!(bitband.unwrap_err().to_string(), "ram overlaps the bit-band alias region at 0x22000000");
+
+        let kind = Machine::parse(r#"
+            [[peripheral]]
+            name = "uart0"
+            kind = "uart"
+            base = 0x40002000
+            size = 0x1000
+        "#, Path::new("."));
+
+        assert!(kind.unwrap_err().to_string().contains("expected `stub`"));
+
+        assert!(Machine::parse("[[memory]]\nname = \"ram\"\nbase = 0\nsize = 4\npermissions = \"rwz\"", Path::new(".")).is_err());
+    }
+}
@@ -1,9 +1,7 @@
-mod processor;
-mod memory;
-mod bus;
 mod tui;
 
-use processor::Processor;
+use cortex_m4::processor::{Processor, UndefinedPolicy};
+use cortex_m4::machine::Machine;
 use tui::Tui;
 
 use clap::{Parser, Subcommand};
@@ -19,6 +17,14 @@ struct Args {
 
     #[arg(long, short, action)]
     debug: bool,
+
+    /// stop instead of raising a usagefault on undefined instructions
+    #[arg(long, action)]
+    stop_on_undefined: bool,
+
+    /// a built-in machine or a toml description of the memory map and peripherals
+    #[arg(long, default_value = "nrf52833")]
+    machine: String,
 }
 
 #[derive(Subcommand, Debug)]
@@ -39,27 +45,42 @@ enum Command {
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
+    let machine = match Machine::builtin(&args.machine) {
+        Some(machine) => machine,
+        None => Machine::load(&args.machine)?,
+    };
+
+    let mut processor = Processor::with_machine(&machine);
+
+    processor.undefined = if args.stop_on_undefined { UndefinedPolicy::Stop } else { UndefinedPolicy::Fault };
+
     match args.command {
         Command::Interactive { path } => {
             let rom = fs::read(path)?;
 
-            let mut tui = Tui::new()?;
+            processor.flash(&rom)?;
 
-            tui.flash(&rom)?;
+            let mut tui = Tui::new(processor)?;
 
             tui.run()?;
         },
         Command::Minimal { path } => {
             let rom = fs::read(path)?;
 
-            let mut processor = Processor::new();
-
             processor.flash(&rom)?;
 
             processor.reset();
 
             for _ in 0..12 {
-                processor.step();
+                if let Some(stop) = processor.step() {
+                    println!("stopped: {}", stop);
+
+                    break;
+                }
+            }
+
+            if processor.locked_up() {
+                return Err("firmware locked up".into());
             }
 
             // TODO: finish the minimal interface
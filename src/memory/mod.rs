@@ -1,23 +1,70 @@
 use crate::bus::{DataBus, BitSize};
 
+use serde::Deserialize;
+
 use std::mem;
 
 
+// what the processor may do with a memory region, written as "rwx" with dashes for denied accesses
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Permissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl Permissions {
+    pub fn all() -> Permissions {
+        Permissions {
+            read: true,
+            write: true,
+            execute: true,
+        }
+    }
+}
+
+impl TryFrom<String> for Permissions {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Permissions, String> {
+        match value.as_bytes() {
+            [read @ (b'r' | b'-'), write @ (b'w' | b'-'), execute @ (b'x' | b'-')] => Ok(Permissions {
+                read: *read == b'r',
+                write: *write == b'w',
+                execute: *execute == b'x',
+            }),
+            _ => Err(format!("invalid permissions: {}", value)),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Memory {
     start: usize,
     data: Vec<u8>,
+    pub permissions: Permissions,
 }
 
 impl Memory {
-    pub fn new(start: usize, capacity: usize) -> Memory {
+    pub fn new(start: usize, capacity: usize, permissions: Permissions) -> Memory {
         Memory {
             start,
             data: vec![0; capacity],
+            permissions,
         }
     }
 
     fn offset(&self, addr: usize) -> usize { addr - self.start }
+
+    pub fn contains(&self, addr: usize, size: usize) -> bool {
+        addr >= self.start && addr + size <= self.start + self.data.len()
+    }
+
+    // copies a backing image to the start of the region
+    pub fn load(&mut self, data: &[u8]) {
+        self.data[..data.len()].copy_from_slice(data);
+    }
 }
 
 impl DataBus for Memory {
@@ -41,7 +88,7 @@ mod tests {
 
     #[test]
     fn ram() {
-        let mut memory = Memory::new(0xff, 0xffff);
+        let mut memory = Memory::new(0xff, 0xffff, Permissions::all());
 
         memory.write::<u8>(0xff, 69);
         memory.write::<u16>(0xff1, 1337);
@@ -51,6 +98,12 @@ mod tests {
         assert_eq!(memory.read::<u16>(0xff1), 1337);
         assert_eq!(memory.read::<u32>(0xff3), 69420);
     }
-}
-
 
+    #[test]
+    fn permissions() {
+        assert_eq!(Permissions::try_from(String::from("r-x")), Ok(Permissions { read: true, write: false, execute: true }));
+        assert_eq!(Permissions::try_from(String::from("rw-")), Ok(Permissions { read: true, write: true, execute: false }));
+        assert!(Permissions::try_from(String::from("rwxx")).is_err());
+        assert!(Permissions::try_from(String::from("xwr")).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use super::instruction::{ShiftKind, ParallelOp, ParallelPrefix};
+
+
+// page 43 @ ARMv7M Reference Manual
+pub fn add_with_carry(x: u32, y: u32, carry_in: bool) -> (u32, bool, bool) {
+    let unsigned = x as u64 + y as u64 + carry_in as u64;
+    let signed = x as i32 as i64 + y as i32 as i64 + carry_in as i64;
+
+    let result = unsigned as u32;
+
+    (result, result as u64 != unsigned, result as i32 as i64 != signed)
+}
+
+// page 42 @ ARMv7M Reference Manual
+pub fn shift_c(value: u32, kind: ShiftKind, amount: u32, carry_in: bool) -> (u32, bool) {
+    if amount == 0 {
+        return (value, carry_in);
+    }
+
+    match kind {
+        ShiftKind::Lsl => match amount {
+            1..=31 => (value << amount, (value >> (32 - amount)) & 1 != 0),
+            32 => (0, value & 1 != 0),
+            _ => (0, false),
+        },
+        ShiftKind::Lsr => match amount {
+            1..=31 => (value >> amount, (value >> (amount - 1)) & 1 != 0),
+            32 => (0, value >> 31 != 0),
+            _ => (0, false),
+        },
+        ShiftKind::Asr => match amount {
+            1..=31 => (((value as i32) >> amount) as u32, (value >> (amount - 1)) & 1 != 0),
+            _ => (((value as i32) >> 31) as u32, value >> 31 != 0),
+        },
+        ShiftKind::Ror => {
+            let result = value.rotate_right(amount % 32);
+
+            (result, result >> 31 != 0)
+        },
+        ShiftKind::Rrx => (((carry_in as u32) << 31) | (value >> 1), value & 1 != 0),
+    }
+}
+
+// selects the signed top or bottom halfword of a register for the dsp multiplies
+pub fn half(value: u32, high: bool) -> i64 {
+    match high {
+        true => (value >> 16) as i16 as i64,
+        false => value as i16 as i64,
+    }
+}
+
+// page 45 @ ARMv7M Reference Manual
+pub fn signed_sat_q(value: i64, bits: u32) -> (u32, bool) {
+    let (min, max) = (-(1i64 << (bits - 1)), (1i64 << (bits - 1)) - 1);
+
+    (value.clamp(min, max) as u32, value < min || value > max)
+}
+
+pub fn unsigned_sat_q(value: i64, bits: u32) -> (u32, bool) {
+    let max = (1i64 << bits) - 1;
+
+    (value.clamp(0, max) as u32, value < 0 || value > max)
+}
+
+// the lanes of the parallel instructions, returns the result and the ge bits for the plain signed and unsigned forms
+pub fn parallel(op: ParallelOp, prefix: ParallelPrefix, n: u32, m: u32) -> (u32, Option<u8>) {
+    let (bits, lanes) = match op {
+        ParallelOp::Add8 | ParallelOp::Sub8 => (8, 4),
+        _ => (16, 2),
+    };
+
+    let mask = (1u32 << bits) - 1;
+
+    let lane = |value: u32, index: u32| -> i64 {
+        let value = (value >> (index * bits)) & mask;
+
+        match prefix.signed() {
+            true => ((value << (32 - bits)) as i32 >> (32 - bits)) as i64,
+            false => value as i64,
+        }
+    };
+
+    let (mut result, mut ge) = (0, 0);
+
+    for index in 0..lanes {
+        let (a, b, subtract) = match (op, index) {
+            (ParallelOp::Asx, 0) => (lane(n, 0), lane(m, 1), true),
+            (ParallelOp::Asx, _) => (lane(n, 1), lane(m, 0), false),
+            (ParallelOp::Sax, 0) => (lane(n, 0), lane(m, 1), false),
+            (ParallelOp::Sax, _) => (lane(n, 1), lane(m, 0), true),
+            (ParallelOp::Sub16 | ParallelOp::Sub8, _) => (lane(n, index), lane(m, index), true),
+            _ => (lane(n, index), lane(m, index), false),
+        };
+
+        let sum = if subtract { a - b } else { a + b };
+
+        let (value, greater_equal) = match prefix {
+            ParallelPrefix::Signed => (sum as u32, sum >= 0),
+            ParallelPrefix::Unsigned if subtract => (sum as u32, sum >= 0),
+            ParallelPrefix::Unsigned => (sum as u32, sum >= 1 << bits),
+            ParallelPrefix::Saturating => (signed_sat_q(sum, bits).0, false),
+            ParallelPrefix::UnsignedSaturating => (unsigned_sat_q(sum, bits).0, false),
+            ParallelPrefix::Halving | ParallelPrefix::UnsignedHalving => ((sum >> 1) as u32, false),
+        };
+
+        result |= (value & mask) << (index * bits);
+
+        if greater_equal {
+            ge |= ((1u8 << (bits / 8)) - 1) << (index * bits / 8);
+        }
+    }
+
+    match prefix {
+        ParallelPrefix::Signed | ParallelPrefix::Unsigned => (result, Some(ge)),
+        _ => (result, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_with_carry_flags() {
+        assert_eq!(add_with_carry(1, 2, false), (3, false, false));
+        assert_eq!(add_with_carry(0xffffffff, 1, false), (0, true, false));
+        assert_eq!(add_with_carry(0x7fffffff, 1, false), (0x80000000, false, true));
+        assert_eq!(add_with_carry(5, !3, true), (2, true, false));
+        assert_eq!(add_with_carry(3, !5, true), (0xfffffffe, false, false));
+    }
+
+    #[test]
+    fn shift_carry_out() {
+        assert_eq!(shift_c(0x80000001, ShiftKind::Lsl, 1, false), (2, true));
+        assert_eq!(shift_c(0x80000001, ShiftKind::Lsl, 32, false), (0, true));
+        assert_eq!(shift_c(0x80000001, ShiftKind::Lsl, 33, true), (0, false));
+        assert_eq!(shift_c(0x80000001, ShiftKind::Lsr, 1, false), (0x40000000, true));
+        assert_eq!(shift_c(0x80000001, ShiftKind::Lsr, 32, false), (0, true));
+        assert_eq!(shift_c(0x80000000, ShiftKind::Asr, 4, false), (0xf8000000, false));
+        assert_eq!(shift_c(0x80000000, ShiftKind::Asr, 40, false), (0xffffffff, true));
+        assert_eq!(shift_c(0x00000001, ShiftKind::Ror, 1, false), (0x80000000, true));
+        assert_eq!(shift_c(0x00000001, ShiftKind::Rrx, 1, true), (0x80000000, true));
+        assert_eq!(shift_c(0x12345678, ShiftKind::Lsl, 0, true), (0x12345678, true));
+    }
+
+    #[test]
+    fn saturation() {
+        assert_eq!(signed_sat_q(200, 8), (127, true));
+        assert_eq!(signed_sat_q(-200, 8), (-128i32 as u32, true));
+        assert_eq!(signed_sat_q(-5, 8), (-5i32 as u32, false));
+        assert_eq!(signed_sat_q(0x80000000, 32), (0x7fffffff, true));
+        assert_eq!(unsigned_sat_q(-1, 8), (0, true));
+        assert_eq!(unsigned_sat_q(300, 8), (255, true));
+        assert_eq!(unsigned_sat_q(0xffffffff, 32), (0xffffffff, false));
+    }
+
+    #[test]
+    fn parallel_lanes() {
+        assert_eq!(parallel(ParallelOp::Add16, ParallelPrefix::Signed, 0x7fff0001, 0x0001fffe), (0x8000ffff, Some(0b1100)));
+        assert_eq!(parallel(ParallelOp::Add8, ParallelPrefix::Unsigned, 0xff0180ff, 0x01017f01), (0x0002ff00, Some(0b1001)));
+        assert_eq!(parallel(ParallelOp::Sub8, ParallelPrefix::Unsigned, 0x01020304, 0x02020202), (0xff000102, Some(0b0111)));
+        assert_eq!(parallel(ParallelOp::Add16, ParallelPrefix::Saturating, 0x7fff8000, 0x0001ffff), (0x7fff8000, None));
+        assert_eq!(parallel(ParallelOp::Sub8, ParallelPrefix::UnsignedSaturating, 0x01020304, 0x02020202), (0x00000102, None));
+        assert_eq!(parallel(ParallelOp::Add16, ParallelPrefix::UnsignedHalving, 0xffff0003, 0xffff0001), (0xffff0002, None));
+        assert_eq!(parallel(ParallelOp::Asx, ParallelPrefix::Signed, 0x00050003, 0x00010002), (0x00070002, Some(0b1111)));
+        assert_eq!(parallel(ParallelOp::Sax, ParallelPrefix::Halving, 0x00050003, 0x00010002), (0x00010002, None));
+    }
+}
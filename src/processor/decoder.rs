@@ -16,62 +16,877 @@ impl Thumb32 {
         }
     }
 
+    fn reg(opcode: u32, bit: u8) -> u8 {
+        (opcode.get(bit..bit + 4) >> bit) as u8
+    }
+
+    // page 137 @ ARMv7M Reference Manual
+    fn expand_imm(imm12: u32) -> (u32, Option<bool>) {
+        let imm8 = imm12.get(0..8);
+
+        match imm12.get(10..12) {
+            0 => match imm12.get(8..10) >> 8 {
+                0b00 => (imm8, None),
+                0b01 => ((imm8 << 16) | imm8, None),
+                0b10 => ((imm8 << 24) | (imm8 << 8), None),
+                _ => ((imm8 << 24) | (imm8 << 16) | (imm8 << 8) | imm8, None),
+            },
+            _ => {
+                let imm32 = (0x80 | imm12.get(0..7)).rotate_right(imm12.get(7..12) >> 7);
+
+                (imm32, Some(imm32 >> 31 != 0))
+            },
+        }
+    }
+
+    // shared by the modified immediate and shifted register groups, page 136 and 150 @ ARMv7M Reference Manual
+    fn data_processing(opcode: u32, operand: Operand) -> InstructionKind {
+        let (rd, rn) = (Thumb32::reg(opcode, 8), Thumb32::reg(opcode, 16));
+        let setflags = opcode.get(20..21) != 0;
+
+        match (opcode.get(21..25) >> 21, rd, rn) {
+            (0b0000, 15, _) if setflags => InstructionKind::Tst { rn, operand },
+            (0b0000, _, _) => InstructionKind::And { rd, rn, operand, setflags },
+            (0b0001, _, _) => InstructionKind::Bic { rd, rn, operand, setflags },
+            (0b0010, _, 15) => InstructionKind::Mov { rd, operand, setflags },
+            (0b0010, _, _) => InstructionKind::Orr { rd, rn, operand, setflags },
+            (0b0011, _, 15) => InstructionKind::Mvn { rd, operand, setflags },
+            (0b0011, _, _) => InstructionKind::Orn { rd, rn, operand, setflags },
+            (0b0100, 15, _) if setflags => InstructionKind::Teq { rn, operand },
+            (0b0100, _, _) => InstructionKind::Eor { rd, rn, operand, setflags },
+            (0b1000, 15, _) if setflags => InstructionKind::Cmn { rn, operand },
+            (0b1000, _, _) => InstructionKind::Add { rd, rn, operand, setflags },
+            (0b1010, _, _) => InstructionKind::Adc { rd, rn, operand, setflags },
+            (0b1011, _, _) => InstructionKind::Sbc { rd, rn, operand, setflags },
+            (0b1101, 15, _) if setflags => InstructionKind::Cmp { rn, operand },
+            (0b1101, _, _) => InstructionKind::Sub { rd, rn, operand, setflags },
+            (0b1110, _, _) => InstructionKind::Rsb { rd, rn, operand, setflags },
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 136 @ ARMv7M Reference Manual
+    fn modified_immediate(opcode: u32) -> InstructionKind {
+        let imm12 = (opcode.get(26..27) >> 15) | (opcode.get(12..15) >> 4) | opcode.get(0..8);
+
+        let (imm32, carry) = Thumb32::expand_imm(imm12);
+
+        Thumb32::data_processing(opcode, Operand::Imm { imm32, carry })
+    }
+
+    // page 150 @ ARMv7M Reference Manual
+    fn shifted_register(opcode: u32) -> InstructionKind {
+        let imm5 = (opcode.get(12..15) >> 10) | (opcode.get(6..8) >> 6);
+
+        let operand = Operand::Reg {
+            rm: Thumb32::reg(opcode, 0),
+            shift: Shift::decode((opcode.get(4..6) >> 4) as u8, imm5 as u8),
+        };
+
+        match (opcode.get(21..25) >> 21, opcode.get(20..21) != 0) {
+            (0b0110, false) => InstructionKind::Pkh {
+                rd: Thumb32::reg(opcode, 8),
+                rn: Thumb32::reg(opcode, 16),
+                operand,
+                tb: opcode.get(5..6) != 0,
+            },
+            _ => Thumb32::data_processing(opcode, operand),
+        }
+    }
+
+    // page 139 @ ARMv7M Reference Manual
+    fn plain_immediate(opcode: u32) -> InstructionKind {
+        let (rd, rn) = (Thumb32::reg(opcode, 8), Thumb32::reg(opcode, 16));
+
+        let imm12 = (opcode.get(26..27) >> 15) | (opcode.get(12..15) >> 4) | opcode.get(0..8);
+        let imm16 = (opcode.get(16..20) >> 4) | imm12;
+        let imm5 = (opcode.get(12..15) >> 10) | (opcode.get(6..8) >> 6);
+
+        let op = opcode.get(20..25) >> 20;
+
+        match (op, rn) {
+            (0b00000, 15) => InstructionKind::Adr { rd, imm32: imm12, add: true },
+            (0b00000, _) => InstructionKind::Add { rd, rn, operand: Operand::imm(imm12), setflags: false },
+            (0b00100, _) => InstructionKind::Mov { rd, operand: Operand::imm(imm16), setflags: false },
+            (0b01010, 15) => InstructionKind::Adr { rd, imm32: imm12, add: false },
+            (0b01010, _) => InstructionKind::Sub { rd, rn, operand: Operand::imm(imm12), setflags: false },
+            (0b01100, _) => InstructionKind::Movt { rd, imm16: imm16 as u16 },
+            (0b10010 | 0b11010, _) if imm5 == 0 => InstructionKind::Sat16 {
+                rd,
+                saturate_to: opcode.get(0..4) as u8 + (op == 0b10010) as u8,
+                rn,
+                unsigned: op == 0b11010,
+            },
+            (0b10100 | 0b11100, _) if opcode.get(0..5) + 1 + imm5 <= 32 => InstructionKind::Bfx {
+                rd,
+                rn,
+                lsb: imm5 as u8,
+                width: opcode.get(0..5) as u8 + 1,
+                signed: op == 0b10100,
+            },
+            (0b10110, _) if opcode.get(0..5) >= imm5 => InstructionKind::Bfi {
+                rd,
+                rn: Some(rn).filter(|rn| *rn != 15),
+                lsb: imm5 as u8,
+                width: (opcode.get(0..5) - imm5) as u8 + 1,
+            },
+            (0b10000 | 0b10010 | 0b11000 | 0b11010, _) => InstructionKind::Sat {
+                rd,
+                saturate_to: opcode.get(0..5) as u8 + (op & 0b01000 == 0) as u8,
+                operand: Operand::Reg { rm: rn, shift: Shift::decode((opcode.get(21..22) >> 20) as u8, imm5 as u8) },
+                unsigned: op & 0b01000 != 0,
+            },
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 140 @ ARMv7M Reference Manual
+    fn branches_misc(opcode: u32) -> InstructionKind {
+        let s = opcode.get(26..27) >> 26;
+        let (j1, j2) = (opcode.get(13..14) >> 13, opcode.get(11..12) >> 11);
+
+        match (opcode.get(12..15) >> 12, opcode.get(20..27) >> 20) {
+            (0b000 | 0b010, 0b0111010) => match (opcode.get(8..11) >> 8, opcode.get(0..8)) {
+                (0b000, 0) => InstructionKind::Nop,
+                (0b000, 1) => InstructionKind::Yield,
+                (0b000, 2) => InstructionKind::Wfe,
+                (0b000, 3) => InstructionKind::Wfi,
+                (0b000, 4) => InstructionKind::Sev,
+                (0b000, _) => InstructionKind::Nop,
+                _ => InstructionKind::Undefined,
+            },
+            // page 141 @ ARMv7M Reference Manual
+            (0b000 | 0b010, 0b0111011) => match (opcode.get(4..8) >> 4, opcode.get(0..4) as u8) {
+                (0b0010, _) => InstructionKind::Clrex,
+                (0b0100, option) => InstructionKind::Dsb { option },
+                (0b0101, option) => InstructionKind::Dmb { option },
+                (0b0110, option) => InstructionKind::Isb { option },
+                _ => InstructionKind::Undefined,
+            },
+            (0b000 | 0b010, 0b0111000 | 0b0111001) if opcode.get(8..10) == 0 => InstructionKind::Msr {
+                rn: Thumb32::reg(opcode, 16),
+                sysm: opcode.get(0..8) as u8,
+                mask: (opcode.get(10..12) >> 10) as u8,
+            },
+            (0b000 | 0b010, 0b0111110 | 0b0111111) => InstructionKind::Mrs {
+                rd: Thumb32::reg(opcode, 8),
+                sysm: opcode.get(0..8) as u8,
+            },
+            (0b000 | 0b010, op) if op & 0b0111000 == 0b0111000 => InstructionKind::Undefined,
+            (0b000 | 0b010, op) => {
+                let imm21 = (s << 20) | (j2 << 19) | (j1 << 18) | (opcode.get(16..22) >> 4) | (opcode.get(0..11) << 1);
+
+                InstructionKind::B {
+                    cond: Condition::from((op >> 2) as u8 & 0xf),
+                    imm32: imm21.extend(21),
+                }
+            },
+            (op1, _) if op1 & 0b001 != 0 => {
+                let (i1, i2) = (!(j1 ^ s) & 1, !(j2 ^ s) & 1);
+                let imm25 = (s << 24) | (i1 << 23) | (i2 << 22) | (opcode.get(16..26) >> 4) | (opcode.get(0..11) << 1);
+
+                match op1 & 0b100 {
+                    0 => InstructionKind::B { cond: Condition::Al, imm32: imm25.extend(25) },
+                    _ => InstructionKind::Bl { imm32: imm25.extend(25) },
+                }
+            },
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 142 @ ARMv7M Reference Manual
+    fn load_store_multiple(opcode: u32) -> InstructionKind {
+        let rn = Thumb32::reg(opcode, 16);
+        let registers = opcode.get(0..16) as u16;
+        let wback = opcode.get(21..22) != 0;
+
+        match (opcode.get(23..25) >> 23, opcode.get(20..21) != 0) {
+            (0b01, false) => InstructionKind::Stm { rn, registers, wback },
+            (0b01, true) if wback && rn == 13 => InstructionKind::Pop { registers },
+            (0b01, true) => InstructionKind::Ldm { rn, registers, wback },
+            (0b10, false) if wback && rn == 13 => InstructionKind::Push { registers },
+            (0b10, false) => InstructionKind::Stmdb { rn, registers, wback },
+            (0b10, true) => InstructionKind::Ldmdb { rn, registers, wback },
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 143 @ ARMv7M Reference Manual
+    fn load_store_dual(opcode: u32) -> InstructionKind {
+        let (rt, rt2, rn) = (Thumb32::reg(opcode, 12), Thumb32::reg(opcode, 8), Thumb32::reg(opcode, 16));
+
+        let address = Address {
+            rn,
+            offset: Offset::Imm(opcode.get(0..8) << 2),
+            index: opcode.get(24..25) != 0,
+            add: opcode.get(23..24) != 0,
+            wback: opcode.get(21..22) != 0,
+        };
+
+        let exclusive = |imm32| Address::new(rn, Offset::Imm(imm32));
+
+        match (opcode.get(23..25) >> 23, opcode.get(20..22) >> 20, opcode.get(4..8) >> 4) {
+            (0b00, 0b00, _) => InstructionKind::Strex { rd: rt2, rt, address: exclusive(opcode.get(0..8) << 2), width: Width::Word },
+            (0b00, 0b01, _) => InstructionKind::Ldrex { rt, address: exclusive(opcode.get(0..8) << 2), width: Width::Word },
+            (0b01, 0b00, 0b0100) => InstructionKind::Strex { rd: Thumb32::reg(opcode, 0), rt, address: exclusive(0), width: Width::Byte },
+            (0b01, 0b00, 0b0101) => InstructionKind::Strex { rd: Thumb32::reg(opcode, 0), rt, address: exclusive(0), width: Width::Halfword },
+            (0b01, 0b01, 0b0100) => InstructionKind::Ldrex { rt, address: exclusive(0), width: Width::Byte },
+            (0b01, 0b01, 0b0101) => InstructionKind::Ldrex { rt, address: exclusive(0), width: Width::Halfword },
+            (0b01, 0b01, 0b0000) => InstructionKind::Tbb { rn, rm: Thumb32::reg(opcode, 0), half: false },
+            (0b01, 0b01, 0b0001) => InstructionKind::Tbb { rn, rm: Thumb32::reg(opcode, 0), half: true },
+            (0b00 | 0b01, 0b10, _) | (0b10 | 0b11, 0b00 | 0b10, _) => InstructionKind::Strd { rt, rt2, address },
+            (0b00 | 0b01, 0b11, _) | (0b10 | 0b11, 0b01 | 0b11, _) => InstructionKind::Ldrd { rt, rt2, address },
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 144 to 149 @ ARMv7M Reference Manual
+    fn load_store_single(opcode: u32) -> InstructionKind {
+        let (rt, rn) = (Thumb32::reg(opcode, 12), Thumb32::reg(opcode, 16));
+        let (load, signed) = (opcode.get(20..21) != 0, opcode.get(24..25) != 0);
+
+        let width = match opcode.get(21..23) >> 21 {
+            0b00 => Width::Byte,
+            0b01 => Width::Halfword,
+            0b10 if !signed => Width::Word,
+            _ => return InstructionKind::Undefined,
+        };
+
+        let address = match (rn, opcode.get(23..24) != 0, opcode.get(6..12) >> 6) {
+            (15, add, _) if load => Address { add, ..Address::new(15, Offset::Imm(opcode.get(0..12))) },
+            (15, _, _) => return InstructionKind::Undefined,
+            (_, true, _) => Address::new(rn, Offset::Imm(opcode.get(0..12))),
+            (_, false, 0b000000) => Address::new(rn, Offset::Reg {
+                rm: Thumb32::reg(opcode, 0),
+                shift: (opcode.get(4..6) >> 4) as u8,
+            }),
+            (_, false, op2) if op2 & 0b100100 == 0b100100 || op2 & 0b111100 == 0b110000 || op2 & 0b111100 == 0b111000 => Address {
+                rn,
+                offset: Offset::Imm(opcode.get(0..8)),
+                index: opcode.get(10..11) != 0,
+                add: opcode.get(9..10) != 0,
+                wback: opcode.get(8..9) != 0,
+            },
+            _ => return InstructionKind::Undefined,
+        };
+
+        match (load, rt, width) {
+            (false, _, _) if signed => InstructionKind::Undefined,
+            (false, _, _) => InstructionKind::Str { rt, address, width },
+            (true, 15, Width::Byte | Width::Halfword) => InstructionKind::Pld { address },
+            (true, _, _) => InstructionKind::Ldr { rt, address, width, signed },
+        }
+    }
+
+    // page 151 @ ARMv7M Reference Manual
+    fn data_processing_register(opcode: u32) -> InstructionKind {
+        let (rd, rn, rm) = (Thumb32::reg(opcode, 8), Thumb32::reg(opcode, 16), Thumb32::reg(opcode, 0));
+
+        match (opcode.get(20..24) >> 20, opcode.get(4..8) >> 4) {
+            (op1, 0) if op1 & 0b1000 == 0 => InstructionKind::ShiftReg {
+                kind: match op1 >> 1 {
+                    0b00 => ShiftKind::Lsl,
+                    0b01 => ShiftKind::Lsr,
+                    0b10 => ShiftKind::Asr,
+                    _ => ShiftKind::Ror,
+                },
+                rd,
+                rn,
+                rm,
+                setflags: op1 & 1 != 0,
+            },
+            (0b0000 | 0b0001 | 0b0100 | 0b0101, op2) if op2 & 0b1000 != 0 => InstructionKind::Extend {
+                rd,
+                rn: Some(rn).filter(|rn| *rn != 15),
+                rm,
+                rotation: ((op2 & 0b11) * 8) as u8,
+                width: if opcode.get(22..23) != 0 { Width::Byte } else { Width::Halfword },
+                signed: opcode.get(20..21) == 0,
+            },
+            (0b0010 | 0b0011, op2) if op2 & 0b1000 != 0 => InstructionKind::Extend16 {
+                rd,
+                rn: Some(rn).filter(|rn| *rn != 15),
+                rm,
+                rotation: ((op2 & 0b11) * 8) as u8,
+                signed: opcode.get(20..21) == 0,
+            },
+            (op1, op2) if op1 & 0b1000 != 0 && op2 & 0b1000 == 0 => Thumb32::parallel(opcode),
+            (op1, op2) if op1 & 0b1100 == 0b1000 && op2 & 0b1100 == 0b1000 => Thumb32::miscellaneous(opcode),
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 152 and 153 @ ARMv7M Reference Manual
+    fn parallel(opcode: u32) -> InstructionKind {
+        let op = match opcode.get(20..23) >> 20 {
+            0b001 => ParallelOp::Add16,
+            0b010 => ParallelOp::Asx,
+            0b110 => ParallelOp::Sax,
+            0b101 => ParallelOp::Sub16,
+            0b000 => ParallelOp::Add8,
+            0b100 => ParallelOp::Sub8,
+            _ => return InstructionKind::Undefined,
+        };
+
+        let prefix = match (opcode.get(6..7) != 0, opcode.get(4..6) >> 4) {
+            (false, 0b00) => ParallelPrefix::Signed,
+            (false, 0b01) => ParallelPrefix::Saturating,
+            (false, 0b10) => ParallelPrefix::Halving,
+            (true, 0b00) => ParallelPrefix::Unsigned,
+            (true, 0b01) => ParallelPrefix::UnsignedSaturating,
+            (true, 0b10) => ParallelPrefix::UnsignedHalving,
+            _ => return InstructionKind::Undefined,
+        };
+
+        InstructionKind::Parallel {
+            op,
+            prefix,
+            rd: Thumb32::reg(opcode, 8),
+            rn: Thumb32::reg(opcode, 16),
+            rm: Thumb32::reg(opcode, 0),
+        }
+    }
+
+    // page 154 @ ARMv7M Reference Manual
+    fn miscellaneous(opcode: u32) -> InstructionKind {
+        let (rd, rn, rm) = (Thumb32::reg(opcode, 8), Thumb32::reg(opcode, 16), Thumb32::reg(opcode, 0));
+        let op2 = opcode.get(4..6) >> 4;
+
+        match opcode.get(20..22) >> 20 {
+            0b00 => InstructionKind::Qadd { rd, rm, rn, double: op2 & 1 != 0, subtract: op2 & 2 != 0 },
+            0b01 => InstructionKind::Reverse {
+                op: match op2 {
+                    0b00 => ReverseOp::Rev,
+                    0b01 => ReverseOp::Rev16,
+                    0b10 => ReverseOp::Rbit,
+                    _ => ReverseOp::Revsh,
+                },
+                rd,
+                rm,
+            },
+            0b10 if op2 == 0 => InstructionKind::Sel { rd, rn, rm },
+            0b11 if op2 == 0 => InstructionKind::Clz { rd, rm },
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 155 @ ARMv7M Reference Manual
+    fn multiply(opcode: u32) -> InstructionKind {
+        let (rd, rn, rm) = (Thumb32::reg(opcode, 8), Thumb32::reg(opcode, 16), Thumb32::reg(opcode, 0));
+        let ra = Some(Thumb32::reg(opcode, 12)).filter(|ra| *ra != 15);
+        let (high, low) = (opcode.get(5..6) != 0, opcode.get(4..5) != 0);
+
+        if opcode.get(6..8) != 0 {
+            return InstructionKind::Undefined;
+        }
+
+        match (opcode.get(20..23) >> 20, high, ra) {
+            (0b000, false, None) if !low => InstructionKind::Mul { rd, rn, rm, setflags: false },
+            (0b000, false, Some(ra)) => InstructionKind::Mla { rd, rn, rm, ra, subtract: low },
+            (0b001, _, _) => InstructionKind::Smla { rd, rn, rm, ra, n_high: high, m_high: low },
+            (0b010, false, _) => InstructionKind::Smlad { rd, rn, rm, ra, exchange: low, subtract: false },
+            (0b011, false, _) => InstructionKind::Smlaw { rd, rn, rm, ra, m_high: low },
+            (0b100, false, _) => InstructionKind::Smlad { rd, rn, rm, ra, exchange: low, subtract: true },
+            (0b101, false, _) => InstructionKind::Smmla { rd, rn, rm, ra, round: low, subtract: false },
+            (0b110, false, Some(_)) => InstructionKind::Smmla { rd, rn, rm, ra, round: low, subtract: true },
+            (0b111, false, _) if !low => InstructionKind::Usad8 { rd, rn, rm, ra },
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 156 @ ARMv7M Reference Manual
+    fn long_multiply(opcode: u32) -> InstructionKind {
+        let (rdlo, rdhi) = (Thumb32::reg(opcode, 12), Thumb32::reg(opcode, 8));
+        let (rn, rm) = (Thumb32::reg(opcode, 16), Thumb32::reg(opcode, 0));
+        let (high, low) = (opcode.get(5..6) != 0, opcode.get(4..5) != 0);
+
+        match (opcode.get(20..23) >> 20, opcode.get(4..8) >> 4) {
+            (0b000, 0b0000) => InstructionKind::MulLong { rdlo, rdhi, rn, rm, signed: true, accumulate: false },
+            (0b001, 0b1111) => InstructionKind::Div { rd: rdhi, rn, rm, signed: true },
+            (0b010, 0b0000) => InstructionKind::MulLong { rdlo, rdhi, rn, rm, signed: false, accumulate: false },
+            (0b011, 0b1111) => InstructionKind::Div { rd: rdhi, rn, rm, signed: false },
+            (0b100, 0b0000) => InstructionKind::MulLong { rdlo, rdhi, rn, rm, signed: true, accumulate: true },
+            (0b100, 0b1000..=0b1011) => InstructionKind::Smlal { rdlo, rdhi, rn, rm, n_high: high, m_high: low },
+            (0b100, 0b1100 | 0b1101) => InstructionKind::Smlald { rdlo, rdhi, rn, rm, exchange: low, subtract: false },
+            (0b101, 0b1100 | 0b1101) => InstructionKind::Smlald { rdlo, rdhi, rn, rm, exchange: low, subtract: true },
+            (0b110, 0b0000) => InstructionKind::MulLong { rdlo, rdhi, rn, rm, signed: false, accumulate: true },
+            (0b110, 0b0110) => InstructionKind::Umaal { rdlo, rdhi, rn, rm },
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    fn single(opcode: u32, bit: u8, extra: u8) -> u8 {
+        (Thumb32::reg(opcode, bit) << 1) | (opcode.get(extra..extra + 1) >> extra) as u8
+    }
+
+    fn double(opcode: u32, bit: u8, extra: u8) -> u8 {
+        ((opcode.get(extra..extra + 1) >> extra) << 4) as u8 | Thumb32::reg(opcode, bit)
+    }
+
+    // page 157 @ ARMv7M Reference Manual
+    fn coprocessor(opcode: u32) -> InstructionKind {
+        if opcode.get(28..29) != 0 || opcode.get(9..12) >> 9 != 0b101 {
+            return InstructionKind::Undefined;
+        }
+
+        match (opcode.get(20..26) >> 20, opcode.get(4..5) != 0) {
+            (op1, _) if op1 & 0b111110 == 0b000000 => InstructionKind::Undefined,
+            (op1, _) if op1 & 0b111110 == 0b000100 => Thumb32::fp_transfer_pair(opcode),
+            (op1, _) if op1 & 0b100000 == 0 => Thumb32::fp_load_store(opcode),
+            (op1, false) if op1 & 0b110000 == 0b100000 => Thumb32::fp_data_processing(opcode),
+            (op1, true) if op1 & 0b110000 == 0b100000 => Thumb32::fp_transfer(opcode),
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 170 @ ARMv7M Reference Manual
+    fn fp_load_store(opcode: u32) -> InstructionKind {
+        let (rn, imm8) = (Thumb32::reg(opcode, 16), opcode.get(0..8));
+        let (index, add, wback, load) = (opcode.get(24..25) != 0, opcode.get(23..24) != 0, opcode.get(21..22) != 0, opcode.get(20..21) != 0);
+
+        let (vd, count) = match opcode.get(8..9) != 0 {
+            true => (FpRegister::double(Thumb32::double(opcode, 12, 22)), imm8 / 2),
+            false => (FpRegister::single(Thumb32::single(opcode, 12, 22)), imm8),
+        };
+
+        let limit = if vd.double { 16 } else { 32 };
+        let last = vd.index as u32 + count;
+
+        match (index, add, wback) {
+            (true, _, false) if vd.index < limit => {
+                let address = Address { add, ..Address::new(rn, Offset::Imm(imm8 << 2)) };
+
+                if load { InstructionKind::Vldr { vd, address } } else { InstructionKind::Vstr { vd, address } }
+            },
+            (false, true, _) | (true, false, true) if count != 0 && last <= limit as u32 => {
+                let count = count as u8;
+
+                if load { InstructionKind::Vldm { rn, vd, count, add, wback } } else { InstructionKind::Vstm { rn, vd, count, add, wback } }
+            },
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 172 @ ARMv7M Reference Manual
+    fn fp_transfer_pair(opcode: u32) -> InstructionKind {
+        let vm = match opcode.get(8..9) != 0 {
+            true => FpRegister::double(Thumb32::double(opcode, 0, 5)),
+            false => FpRegister::single(Thumb32::single(opcode, 0, 5)),
+        };
+
+        match (opcode.get(6..8), opcode.get(4..5) != 0) {
+            (0, true) if vm.double && vm.index < 16 || !vm.double && vm.index < 31 => InstructionKind::VmovCorePair {
+                vm,
+                rt: Thumb32::reg(opcode, 12),
+                rt2: Thumb32::reg(opcode, 16),
+                to_core: opcode.get(20..21) != 0,
+            },
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 171 @ ARMv7M Reference Manual
+    fn fp_transfer(opcode: u32) -> InstructionKind {
+        let (rt, to_core) = (Thumb32::reg(opcode, 12), opcode.get(20..21) != 0);
+
+        match (opcode.get(21..24) >> 21, opcode.get(8..9) != 0, Thumb32::reg(opcode, 16)) {
+            (0b000, false, _) => InstructionKind::VmovCore { sn: Thumb32::single(opcode, 16, 7), rt, to_core },
+            (0b111, false, 0b0001) if to_core => InstructionKind::Vmrs { rt },
+            (0b111, false, 0b0001) => InstructionKind::Vmsr { rt },
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 168 @ ARMv7M Reference Manual
+    fn fp_data_processing(opcode: u32) -> InstructionKind {
+        let (sd, sn, sm) = (Thumb32::single(opcode, 12, 22), Thumb32::single(opcode, 16, 7), Thumb32::single(opcode, 0, 5));
+        let (opc1, opc2) = ((opcode.get(23..24) >> 21) | (opcode.get(20..22) >> 20), Thumb32::reg(opcode, 16));
+        let (op, n) = (opcode.get(6..7) != 0, opcode.get(7..8) != 0);
+
+        // double precision is not part of FPv4-SP
+        if opcode.get(8..9) != 0 {
+            return InstructionKind::Undefined;
+        }
+
+        let vfp = |op| InstructionKind::Vfp { op, sd, sn, sm };
+
+        match (opc1, op) {
+            (0b000, false) => vfp(VfpOp::Mla),
+            (0b000, true) => vfp(VfpOp::Mls),
+            (0b001, false) => vfp(VfpOp::Nmls),
+            (0b001, true) => vfp(VfpOp::Nmla),
+            (0b010, false) => vfp(VfpOp::Mul),
+            (0b010, true) => vfp(VfpOp::Nmul),
+            (0b011, false) => vfp(VfpOp::Add),
+            (0b011, true) => vfp(VfpOp::Sub),
+            (0b100, false) => vfp(VfpOp::Div),
+            (0b101, false) => vfp(VfpOp::Fnms),
+            (0b101, true) => vfp(VfpOp::Fnma),
+            (0b110, false) => vfp(VfpOp::Fma),
+            (0b110, true) => vfp(VfpOp::Fms),
+            (0b111, false) => {
+                let imm8 = (opc2 << 4) as u32 | opcode.get(0..4);
+                let exponent = match imm8 & 0x40 != 0 {
+                    true => 0b0111_1100 | ((imm8 >> 4) & 0b11),
+                    false => 0b1000_0000 | ((imm8 >> 4) & 0b11),
+                };
+
+                InstructionKind::VmovImm { sd, imm32: ((imm8 >> 7) << 31) | (exponent << 23) | ((imm8 & 0xf) << 19) }
+            },
+            (0b111, true) => match (opc2, n) {
+                (0b0000, false) => InstructionKind::VfpUnary { op: VfpUnaryOp::Mov, sd, sm },
+                (0b0000, true) => InstructionKind::VfpUnary { op: VfpUnaryOp::Abs, sd, sm },
+                (0b0001, false) => InstructionKind::VfpUnary { op: VfpUnaryOp::Neg, sd, sm },
+                (0b0001, true) => InstructionKind::VfpUnary { op: VfpUnaryOp::Sqrt, sd, sm },
+                (0b0100, signal) => InstructionKind::Vcmp { sd, sm: Some(sm), signal },
+                (0b0101, signal) => InstructionKind::Vcmp { sd, sm: None, signal },
+                (0b0010 | 0b0011, top) => InstructionKind::VcvtHalf { sd, sm, top, to_half: opc2 & 1 != 0 },
+                (0b1000, signed) => InstructionKind::VcvtInt { sd, sm, signed, to_int: false, round_zero: false },
+                (0b1100 | 0b1101, round_zero) => InstructionKind::VcvtInt { sd, sm, signed: opc2 & 1 != 0, to_int: true, round_zero },
+                (0b1010 | 0b1011 | 0b1110 | 0b1111, sx) => {
+                    let size = if sx { 32 } else { 16 };
+                    let imm5 = (opcode.get(0..4) << 1) | (opcode.get(5..6) >> 5);
+
+                    match size >= imm5 {
+                        true => InstructionKind::VcvtFixed {
+                            sd,
+                            signed: opc2 & 1 == 0,
+                            half: !sx,
+                            fbits: (size - imm5) as u8,
+                            to_fixed: opc2 & 0b100 != 0,
+                        },
+                        false => InstructionKind::Undefined,
+                    }
+                },
+                _ => InstructionKind::Undefined,
+            },
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 135 @ ARMv7M Reference Manual
     pub fn decode(&self, halfword: u16) -> InstructionKind {
-        InstructionKind::Undefined
+        let opcode = ((self.halfword as u32) << 16) | halfword as u32;
+
+        match (opcode.get(27..29) >> 27, opcode.get(20..27) >> 20, opcode.get(15..16) != 0) {
+            (0b01, op2, _) if op2 & 0b1100100 == 0b0000000 => Thumb32::load_store_multiple(opcode),
+            (0b01, op2, _) if op2 & 0b1100100 == 0b0000100 => Thumb32::load_store_dual(opcode),
+            (0b01, op2, _) if op2 & 0b1100000 == 0b0100000 => Thumb32::shifted_register(opcode),
+            (0b01, op2, _) if op2 & 0b1000000 != 0 => Thumb32::coprocessor(opcode),
+            (0b10, op2, false) if op2 & 0b0100000 == 0 => Thumb32::modified_immediate(opcode),
+            (0b10, _, false) => Thumb32::plain_immediate(opcode),
+            (0b10, _, true) => Thumb32::branches_misc(opcode),
+            (0b11, op2, _) if op2 & 0b1110001 == 0b0000000 => Thumb32::load_store_single(opcode),
+            (0b11, op2, _) if op2 & 0b1100111 == 0b0000001 => Thumb32::load_store_single(opcode),
+            (0b11, op2, _) if op2 & 0b1100111 == 0b0000011 => Thumb32::load_store_single(opcode),
+            (0b11, op2, _) if op2 & 0b1100111 == 0b0000101 => Thumb32::load_store_single(opcode),
+            (0b11, op2, _) if op2 & 0b1110000 == 0b0100000 => Thumb32::data_processing_register(opcode),
+            (0b11, op2, _) if op2 & 0b1111000 == 0b0110000 => Thumb32::multiply(opcode),
+            (0b11, op2, _) if op2 & 0b1111000 == 0b0111000 => Thumb32::long_multiply(opcode),
+            (0b11, op2, _) if op2 & 0b1000000 != 0 => Thumb32::coprocessor(opcode),
+            _ => InstructionKind::Undefined,
+        }
     }
 }
 
 pub struct Thumb16 {
     opcode: u16,
+    it: bool,
 }
 
 impl Thumb16 {
-    pub fn new(opcode: u16) -> Thumb16 {
+    // flag setting 16-bit encodings only set the flags outside of an it block
+    pub fn new(opcode: u16, it: bool) -> Thumb16 {
         Thumb16 {
             opcode,
+            it,
         }
     }
 
-    pub fn decode(&self) -> InstructionKind {
-        match self.opcode.get(11..16) {
-            0b0010_0000_0000_0000 => InstructionKind::Mov {
-                register: (self.opcode.get(8..11) >> 8) as u8,
-                source: Source::Imm8((self.opcode.get(0..8)) as u8),
-            },
-            0b0001_1000_0000_0000 => InstructionKind::Add {
-                rm: (self.opcode.get(6..9) >> 6) as u8,
-                rn: (self.opcode.get(3..6) >> 3) as u8,
-                rd: self.opcode.get(0..3) as u8,
-            },
-            0b0100_0000_0000_0000 => match self.opcode.get(7..16) {
-                0b0100_0111_1000_0000 => InstructionKind::Blx {
-                    rm: (self.opcode.get(3..7) >> 3) as u8,
+    fn low(&self, bit: u8) -> u8 {
+        (self.opcode.get(bit..bit + 3) >> bit) as u8
+    }
+
+    // page 129 @ ARMv7M Reference Manual
+    fn shift_add_sub_mov_compare(&self) -> InstructionKind {
+        let imm5 = (self.opcode.get(6..11) >> 6) as u8;
+        let imm8 = self.opcode.get(0..8) as u32;
+
+        match self.opcode.get(9..14) >> 9 {
+            0b00000..=0b01011 => InstructionKind::Mov {
+                rd: self.low(0),
+                operand: Operand::Reg {
+                    rm: self.low(3),
+                    shift: Shift::decode((self.opcode.get(11..13) >> 11) as u8, imm5),
                 },
-                0b0100_0111_0000_0000 => InstructionKind::Bx {
-                    rm: (self.opcode.get(3..7) >> 3) as u8,
+                setflags: !self.it,
+            },
+            0b01100 => InstructionKind::Add {
+                rd: self.low(0),
+                rn: self.low(3),
+                operand: Operand::reg(self.low(6)),
+                setflags: !self.it,
+            },
+            0b01101 => InstructionKind::Sub {
+                rd: self.low(0),
+                rn: self.low(3),
+                operand: Operand::reg(self.low(6)),
+                setflags: !self.it,
+            },
+            0b01110 => InstructionKind::Add {
+                rd: self.low(0),
+                rn: self.low(3),
+                operand: Operand::imm(self.low(6) as u32),
+                setflags: !self.it,
+            },
+            0b01111 => InstructionKind::Sub {
+                rd: self.low(0),
+                rn: self.low(3),
+                operand: Operand::imm(self.low(6) as u32),
+                setflags: !self.it,
+            },
+            0b10000..=0b10011 => InstructionKind::Mov {
+                rd: self.low(8),
+                operand: Operand::imm(imm8),
+                setflags: !self.it,
+            },
+            0b10100..=0b10111 => InstructionKind::Cmp {
+                rn: self.low(8),
+                operand: Operand::imm(imm8),
+            },
+            0b11000..=0b11011 => InstructionKind::Add {
+                rd: self.low(8),
+                rn: self.low(8),
+                operand: Operand::imm(imm8),
+                setflags: !self.it,
+            },
+            _ => InstructionKind::Sub {
+                rd: self.low(8),
+                rn: self.low(8),
+                operand: Operand::imm(imm8),
+                setflags: !self.it,
+            },
+        }
+    }
+
+    // page 130 @ ARMv7M Reference Manual
+    fn data_processing(&self) -> InstructionKind {
+        let (rdn, rm) = (self.low(0), self.low(3));
+
+        match self.opcode.get(6..10) >> 6 {
+            0b0000 => InstructionKind::And { rd: rdn, rn: rdn, operand: Operand::reg(rm), setflags: !self.it },
+            0b0001 => InstructionKind::Eor { rd: rdn, rn: rdn, operand: Operand::reg(rm), setflags: !self.it },
+            0b0010 => InstructionKind::ShiftReg { kind: ShiftKind::Lsl, rd: rdn, rn: rdn, rm, setflags: !self.it },
+            0b0011 => InstructionKind::ShiftReg { kind: ShiftKind::Lsr, rd: rdn, rn: rdn, rm, setflags: !self.it },
+            0b0100 => InstructionKind::ShiftReg { kind: ShiftKind::Asr, rd: rdn, rn: rdn, rm, setflags: !self.it },
+            0b0101 => InstructionKind::Adc { rd: rdn, rn: rdn, operand: Operand::reg(rm), setflags: !self.it },
+            0b0110 => InstructionKind::Sbc { rd: rdn, rn: rdn, operand: Operand::reg(rm), setflags: !self.it },
+            0b0111 => InstructionKind::ShiftReg { kind: ShiftKind::Ror, rd: rdn, rn: rdn, rm, setflags: !self.it },
+            0b1000 => InstructionKind::Tst { rn: rdn, operand: Operand::reg(rm) },
+            0b1001 => InstructionKind::Rsb { rd: rdn, rn: rm, operand: Operand::imm(0), setflags: !self.it },
+            0b1010 => InstructionKind::Cmp { rn: rdn, operand: Operand::reg(rm) },
+            0b1011 => InstructionKind::Cmn { rn: rdn, operand: Operand::reg(rm) },
+            0b1100 => InstructionKind::Orr { rd: rdn, rn: rdn, operand: Operand::reg(rm), setflags: !self.it },
+            0b1101 => InstructionKind::Mul { rd: rdn, rn: rm, rm: rdn, setflags: !self.it },
+            0b1110 => InstructionKind::Bic { rd: rdn, rn: rdn, operand: Operand::reg(rm), setflags: !self.it },
+            _ => InstructionKind::Mvn { rd: rdn, operand: Operand::reg(rm), setflags: !self.it },
+        }
+    }
+
+    // page 131 @ ARMv7M Reference Manual
+    fn special_data_branch(&self) -> InstructionKind {
+        let rdn = ((self.opcode.get(7..8) >> 4) | self.opcode.get(0..3)) as u8;
+        let rm = (self.opcode.get(3..7) >> 3) as u8;
+
+        match self.opcode.get(6..10) >> 6 {
+            0b0000..=0b0011 => InstructionKind::Add {
+                rd: rdn,
+                rn: rdn,
+                operand: Operand::reg(rm),
+                setflags: false,
+            },
+            0b0100..=0b0111 => InstructionKind::Cmp {
+                rn: rdn,
+                operand: Operand::reg(rm),
+            },
+            0b1000..=0b1011 => InstructionKind::Mov {
+                rd: rdn,
+                operand: Operand::reg(rm),
+                setflags: false,
+            },
+            0b1100 | 0b1101 => InstructionKind::Bx {
+                rm,
+            },
+            _ => InstructionKind::Blx {
+                rm,
+            },
+        }
+    }
+
+    // page 132 @ ARMv7M Reference Manual
+    fn load_store_single(&self) -> InstructionKind {
+        let (rt, rn) = (self.low(0), self.low(3));
+        let imm5 = self.opcode.get(6..11) as u32 >> 6;
+
+        let register = Address::new(rn, Offset::Reg { rm: self.low(6), shift: 0 });
+
+        match (self.opcode.get(12..16) >> 12, self.opcode.get(9..12) >> 9) {
+            (0b0101, 0b000) => InstructionKind::Str { rt, address: register, width: Width::Word },
+            (0b0101, 0b001) => InstructionKind::Str { rt, address: register, width: Width::Halfword },
+            (0b0101, 0b010) => InstructionKind::Str { rt, address: register, width: Width::Byte },
+            (0b0101, 0b011) => InstructionKind::Ldr { rt, address: register, width: Width::Byte, signed: true },
+            (0b0101, 0b100) => InstructionKind::Ldr { rt, address: register, width: Width::Word, signed: false },
+            (0b0101, 0b101) => InstructionKind::Ldr { rt, address: register, width: Width::Halfword, signed: false },
+            (0b0101, 0b110) => InstructionKind::Ldr { rt, address: register, width: Width::Byte, signed: false },
+            (0b0101, _) => InstructionKind::Ldr { rt, address: register, width: Width::Halfword, signed: true },
+            (0b0110, 0b000..=0b011) => InstructionKind::Str { rt, address: Address::new(rn, Offset::Imm(imm5 << 2)), width: Width::Word },
+            (0b0110, _) => InstructionKind::Ldr { rt, address: Address::new(rn, Offset::Imm(imm5 << 2)), width: Width::Word, signed: false },
+            (0b0111, 0b000..=0b011) => InstructionKind::Str { rt, address: Address::new(rn, Offset::Imm(imm5)), width: Width::Byte },
+            (0b0111, _) => InstructionKind::Ldr { rt, address: Address::new(rn, Offset::Imm(imm5)), width: Width::Byte, signed: false },
+            (0b1000, 0b000..=0b011) => InstructionKind::Str { rt, address: Address::new(rn, Offset::Imm(imm5 << 1)), width: Width::Halfword },
+            (0b1000, _) => InstructionKind::Ldr { rt, address: Address::new(rn, Offset::Imm(imm5 << 1)), width: Width::Halfword, signed: false },
+            (_, 0b000..=0b011) => InstructionKind::Str {
+                rt: self.low(8),
+                address: Address::new(13, Offset::Imm((self.opcode.get(0..8) as u32) << 2)),
+                width: Width::Word,
+            },
+            _ => InstructionKind::Ldr {
+                rt: self.low(8),
+                address: Address::new(13, Offset::Imm((self.opcode.get(0..8) as u32) << 2)),
+                width: Width::Word,
+                signed: false,
+            },
+        }
+    }
+
+    // page 133 @ ARMv7M Reference Manual
+    fn miscellaneous(&self) -> InstructionKind {
+        let imm7 = self.opcode.get(0..7) as u32;
+
+        match self.opcode.get(5..12) >> 5 {
+            0b0000000..=0b0000011 => InstructionKind::Add {
+                rd: 13,
+                rn: 13,
+                operand: Operand::imm(imm7 << 2),
+                setflags: false,
+            },
+            0b0000100..=0b0000111 => InstructionKind::Sub {
+                rd: 13,
+                rn: 13,
+                operand: Operand::imm(imm7 << 2),
+                setflags: false,
+            },
+            0b0001000..=0b0001111 | 0b0011000..=0b0011111 | 0b1001000..=0b1001111 | 0b1011000..=0b1011111 => InstructionKind::Cbz {
+                rn: self.low(0),
+                imm32: ((self.opcode.get(9..10) >> 3) | (self.opcode.get(3..8) >> 2)) as u32,
+                nonzero: self.opcode.get(11..12) != 0,
+            },
+            0b0010000..=0b0010111 => InstructionKind::Extend {
+                rd: self.low(0),
+                rn: None,
+                rm: self.low(3),
+                rotation: 0,
+                width: if self.opcode.get(6..7) != 0 { Width::Byte } else { Width::Halfword },
+                signed: self.opcode.get(7..8) == 0,
+            },
+            0b1010000..=0b1010011 | 0b1010110..=0b1010111 => InstructionKind::Reverse {
+                op: match self.opcode.get(6..8) >> 6 {
+                    0b00 => ReverseOp::Rev,
+                    0b01 => ReverseOp::Rev16,
+                    _ => ReverseOp::Revsh,
                 },
-                _ => InstructionKind::Undefined,
+                rd: self.low(0),
+                rm: self.low(3),
             },
-            0b1110_0000_0000_0000 => InstructionKind::B {
-                imm11: self.opcode.get(0..11).extend(11),
+            0b0110011 => InstructionKind::Cps {
+                enable: self.opcode.get(4..5) == 0,
+                primask: self.opcode.get(1..2) != 0,
+                faultmask: self.opcode.get(0..1) != 0,
             },
-            0b0100_1000_0000_0000 => InstructionKind::Ldr {
-                rt: (self.opcode.get(8..11) >> 8) as u8,
-                source: Source::Imm32((self.opcode.get(0..8) as u32) << 2),
+            0b0100000..=0b0101111 => InstructionKind::Push {
+                registers: (self.opcode.get(8..9) << 6) | self.opcode.get(0..8),
             },
-            0b0101_1000_0000_0000 => InstructionKind::LdrReg {
-                rm: (self.opcode.get(6..9) >> 6) as u8,
-                rn: (self.opcode.get(3..6) >> 3) as u8,
-                rt: self.opcode.get(0..3) as u8,
+            0b1100000..=0b1101111 => InstructionKind::Pop {
+                registers: (self.opcode.get(8..9) << 7) | self.opcode.get(0..8),
             },
-            0b0110_1000_0000_0000 => InstructionKind::LdrImm {
-                source: Source::Imm8((self.opcode.get(6..11) >> 6) as u8),
-                rn: (self.opcode.get(3..6) >> 3) as u8,
-                rt: self.opcode.get(0..3) as u8,
+            0b1110000..=0b1110111 => InstructionKind::Bkpt {
+                imm8: self.opcode.get(0..8) as u8,
             },
-            0b0110_0000_0000_0000 => InstructionKind::Str {
-                rt: self.opcode.get(0..3) as u8,
-                rn: (self.opcode.get(3..6) >> 3) as u8,
+            0b1111000..=0b1111111 => self.if_then_hints(),
+            _ => InstructionKind::Undefined,
+        }
+    }
+
+    // page 134 @ ARMv7M Reference Manual
+    fn if_then_hints(&self) -> InstructionKind {
+        match (self.opcode.get(4..8) >> 4, self.opcode.get(0..4)) {
+            (0b0000, 0) => InstructionKind::Nop,
+            (0b0001, 0) => InstructionKind::Yield,
+            (0b0010, 0) => InstructionKind::Wfe,
+            (0b0011, 0) => InstructionKind::Wfi,
+            (0b0100, 0) => InstructionKind::Sev,
+            (_, 0) => InstructionKind::Nop,
+            (firstcond, mask) => InstructionKind::It {
+                firstcond: Condition::from(firstcond as u8),
+                mask: mask as u8,
+            },
+        }
+    }
+
+    // page 135 @ ARMv7M Reference Manual
+    fn conditional_branch(&self) -> InstructionKind {
+        match self.opcode.get(8..12) >> 8 {
+            0b1110 => InstructionKind::Undefined,
+            0b1111 => InstructionKind::Svc {
+                imm8: self.opcode.get(0..8) as u8,
+            },
+            cond => InstructionKind::B {
+                cond: Condition::from(cond as u8),
+                imm32: ((self.opcode.get(0..8) as u32) << 1).extend(9),
+            },
+        }
+    }
+
+    pub fn decode(&self) -> InstructionKind {
+        match self.opcode.get(11..16) >> 11 {
+            0b00000..=0b00111 => self.shift_add_sub_mov_compare(),
+            0b01000 => match self.opcode.get(10..11) {
+                0 => self.data_processing(),
+                _ => self.special_data_branch(),
+            },
+            0b01001 => InstructionKind::Ldr {
+                rt: self.low(8),
+                address: Address::new(15, Offset::Imm((self.opcode.get(0..8) as u32) << 2)),
+                width: Width::Word,
+                signed: false,
+            },
+            0b01010..=0b10011 => self.load_store_single(),
+            0b10100 => InstructionKind::Adr {
+                rd: self.low(8),
+                imm32: (self.opcode.get(0..8) as u32) << 2,
+                add: true,
+            },
+            0b10101 => InstructionKind::Add {
+                rd: self.low(8),
+                rn: 13,
+                operand: Operand::imm((self.opcode.get(0..8) as u32) << 2),
+                setflags: false,
+            },
+            0b10110 | 0b10111 => self.miscellaneous(),
+            0b11000 => InstructionKind::Stm {
+                rn: self.low(8),
+                registers: self.opcode.get(0..8),
+                wback: true,
+            },
+            0b11001 => InstructionKind::Ldm {
+                rn: self.low(8),
+                registers: self.opcode.get(0..8),
+                wback: self.opcode.get(0..8) & (1 << self.low(8)) == 0,
+            },
+            0b11010 | 0b11011 => self.conditional_branch(),
+            0b11100 => InstructionKind::B {
+                cond: Condition::Al,
+                imm32: ((self.opcode.get(0..11) as u32) << 1).extend(12),
             },
             _ => InstructionKind::Undefined,
         }
@@ -84,10 +899,10 @@ pub enum Decoder {
 }
 
 impl Decoder {
-    pub fn new(halfword: u16) -> Decoder {
+    pub fn new(halfword: u16, it: bool) -> Decoder {
         match halfword.get(11..16) {
             0b1110_1000_0000_0000 | 0b1111_0000_0000_0000 |  0b1111_1000_0000_0000 => Decoder::Thumb32(Thumb32::new(halfword)),
-            _ => Decoder::Thumb16(Thumb16::new(halfword)),
+            _ => Decoder::Thumb16(Thumb16::new(halfword, it)),
         }
     }
 }
@@ -109,12 +924,12 @@ impl BitVec for u32 {
 }
 
 pub trait SignExtend<T> {
-    fn extend(&self, topbit: i16) -> T;
+    fn extend(&self, bits: u32) -> T;
 }
 
-impl SignExtend<i16> for u16 {
-    fn extend(&self, topbit: i16) -> i16 {
-        *self as i16 | ((1 << (16 - topbit)) - 1) << topbit
+impl SignExtend<i32> for u32 {
+    fn extend(&self, bits: u32) -> i32 {
+        ((*self << (32 - bits)) as i32) >> (32 - bits)
     }
 }
 
@@ -122,13 +937,378 @@ impl SignExtend<i16> for u16 {
 mod tests {
     use super::*;
 
+    fn decode(opcode: u16) -> InstructionKind {
+        match Decoder::new(opcode, false) {
+            Decoder::Thumb16(thumb16) => thumb16.decode(),
+            Decoder::Thumb32(_) => panic!("expected a 16-bit encoding"),
+        }
+    }
+
+    fn disassemble(opcode: u16) -> String {
+        decode(opcode).to_string()
+    }
+
+    fn disassemble32(first: u16, second: u16) -> String {
+        match Decoder::new(first, false) {
+            Decoder::Thumb32(thumb32) => thumb32.decode(second).to_string(),
+            Decoder::Thumb16(_) => panic!("expected a 32-bit encoding"),
+        }
+    }
+
     #[test]
     fn bitvec() {
         let number: u16 = 0b1000_1111_0001_0111;
 
-        println!("{:#018b}", number);
-        println!("{:#018b}", number.get(0..12));
+        assert_eq!(number.get(0..12), 0b0000_1111_0001_0111);
+        assert_eq!(number.get(12..16) >> 12, 0b1000);
     }
-}
 
+    #[test]
+    fn sign_extend() {
+        assert_eq!(0b111u32.extend(3), -1);
+        assert_eq!(0b011u32.extend(3), 3);
+        assert_eq!(0xffeu32.extend(12), -2);
+    }
+
+    #[test]
+    fn shift_add_sub_mov_compare() {
+        assert_eq!(disassemble(0x0088), "lsls r0, r1, #2");
+        assert_eq!(disassemble(0x0008), "movs r0, r1");
+        assert_eq!(disassemble(0x0888), "lsrs r0, r1, #2");
+        assert_eq!(disassemble(0x0808), "lsrs r0, r1, #32");
+        assert_eq!(disassemble(0x1088), "asrs r0, r1, #2");
+        assert_eq!(disassemble(0x1888), "adds r0, r1, r2");
+        assert_eq!(disassemble(0x1a88), "subs r0, r1, r2");
+        assert_eq!(disassemble(0x1cc8), "adds r0, r1, #3");
+        assert_eq!(disassemble(0x1ec8), "subs r0, r1, #3");
+        assert_eq!(disassemble(0x222a), "movs r2, #42");
+        assert_eq!(disassemble(0x2a2a), "cmp r2, #42");
+        assert_eq!(disassemble(0x3201), "adds r2, r2, #1");
+        assert_eq!(disassemble(0x3a01), "subs r2, r2, #1");
+    }
+
+    #[test]
+    fn data_processing() {
+        assert_eq!(disassemble(0x4008), "ands r0, r0, r1");
+        assert_eq!(disassemble(0x4048), "eors r0, r0, r1");
+        assert_eq!(disassemble(0x4088), "lsls r0, r0, r1");
+        assert_eq!(disassemble(0x40c8), "lsrs r0, r0, r1");
+        assert_eq!(disassemble(0x4108), "asrs r0, r0, r1");
+        assert_eq!(disassemble(0x4148), "adcs r0, r0, r1");
+        assert_eq!(disassemble(0x4188), "sbcs r0, r0, r1");
+        assert_eq!(disassemble(0x41c8), "rors r0, r0, r1");
+        assert_eq!(disassemble(0x4208), "tst r0, r1");
+        assert_eq!(disassemble(0x4248), "rsbs r0, r1, #0");
+        assert_eq!(disassemble(0x4288), "cmp r0, r1");
+        assert_eq!(disassemble(0x42c8), "cmn r0, r1");
+        assert_eq!(disassemble(0x4308), "orrs r0, r0, r1");
+        assert_eq!(disassemble(0x4348), "muls r0, r1, r0");
+        assert_eq!(disassemble(0x4388), "bics r0, r0, r1");
+        assert_eq!(disassemble(0x43c8), "mvns r0, r1");
+    }
+
+    #[test]
+    fn special_data_branch() {
+        assert_eq!(disassemble(0x44e8), "add r8, r8, sp");
+        assert_eq!(disassemble(0x4588), "cmp r8, r1");
+        assert_eq!(disassemble(0x46c2), "mov r10, r8");
+        assert_eq!(disassemble(0x4770), "bx lr");
+        assert_eq!(disassemble(0x4798), "blx r3");
+    }
+
+    #[test]
+    fn load_store() {
+        assert_eq!(disassemble(0x4801), "ldr r0, [pc, #4]");
+        assert_eq!(disassemble(0x5088), "str r0, [r1, r2]");
+        assert_eq!(disassemble(0x5288), "strh r0, [r1, r2]");
+        assert_eq!(disassemble(0x5488), "strb r0, [r1, r2]");
+        assert_eq!(disassemble(0x5688), "ldrsb r0, [r1, r2]");
+        assert_eq!(disassemble(0x5888), "ldr r0, [r1, r2]");
+        assert_eq!(disassemble(0x5a88), "ldrh r0, [r1, r2]");
+        assert_eq!(disassemble(0x5c88), "ldrb r0, [r1, r2]");
+        assert_eq!(disassemble(0x5e88), "ldrsh r0, [r1, r2]");
+        assert_eq!(disassemble(0x6048), "str r0, [r1, #4]");
+        assert_eq!(disassemble(0x6848), "ldr r0, [r1, #4]");
+        assert_eq!(disassemble(0x7108), "strb r0, [r1, #4]");
+        assert_eq!(disassemble(0x7908), "ldrb r0, [r1, #4]");
+        assert_eq!(disassemble(0x8088), "strh r0, [r1, #4]");
+        assert_eq!(disassemble(0x8888), "ldrh r0, [r1, #4]");
+        assert_eq!(disassemble(0x9001), "str r0, [sp, #4]");
+        assert_eq!(disassemble(0x9801), "ldr r0, [sp, #4]");
+    }
+
+    #[test]
+    fn load_store_multiple() {
+        assert_eq!(disassemble(0xc10c), "stm r1!, {r2, r3}");
+        assert_eq!(disassemble(0xc90c), "ldm r1!, {r2, r3}");
+        assert_eq!(disassemble(0xc906), "ldm r1, {r1, r2}");
+        assert_eq!(disassemble(0xb510), "push {r4, lr}");
+        assert_eq!(disassemble(0xbd10), "pop {r4, pc}");
+    }
+
+    #[test]
+    fn pc_sp_relative() {
+        assert_eq!(disassemble(0xa001), "adr r0, #4");
+        assert_eq!(disassemble(0xa801), "add r0, sp, #4");
+        assert_eq!(disassemble(0xb082), "sub sp, sp, #8");
+        assert_eq!(disassemble(0xb002), "add sp, sp, #8");
+    }
+
+    #[test]
+    fn branches() {
+        assert_eq!(disassemble(0xd0fe), "beq -4");
+        assert_eq!(disassemble(0xd102), "bne 4");
+        assert_eq!(disassemble(0xe7fe), "b -4");
+        assert_eq!(disassemble(0xe002), "b 4");
+        assert_eq!(disassemble(0xb110), "cbz r0, 4");
+        assert_eq!(disassemble(0xb918), "cbnz r0, 6");
+        assert_eq!(disassemble(0xb310), "cbz r0, 68");
+    }
+
+    #[test]
+    fn exceptions_and_hints() {
+        assert_eq!(disassemble(0xdf05), "svc 5");
+        assert_eq!(disassemble(0xbe01), "bkpt 0x0001");
+        assert_eq!(disassemble(0xbf00), "nop");
+        assert_eq!(disassemble(0xbf10), "yield");
+        assert_eq!(disassemble(0xbf20), "wfe");
+        assert_eq!(disassemble(0xbf30), "wfi");
+        assert_eq!(disassemble(0xbf40), "sev");
+        assert_eq!(disassemble(0xbf08), "it eq");
+        assert_eq!(disassemble(0xbf04), "itt eq");
+        assert_eq!(disassemble(0xbf0c), "ite eq");
+        assert_eq!(disassemble(0xbfb6), "itet lt");
+        assert_eq!(disassemble(0xbf15), "itete ne");
+        assert_eq!(disassemble(0xde00), "undefined");
+    }
+
+    #[test]
+    fn if_then_block() {
+        let decode_it = |opcode| match Decoder::new(opcode, true) {
+            Decoder::Thumb16(thumb16) => thumb16.decode(),
+            Decoder::Thumb32(_) => panic!("expected a 16-bit encoding"),
+        };
+
+        let inst = |kind, condition| Instruction { kind, condition, addr: 0, size: 2 }.to_string();
+
+        assert_eq!(decode_it(0x2101).to_string(), "mov r1, #1");
+        assert_eq!(decode_it(0x1888).to_string(), "add r0, r1, r2");
+        assert_eq!(decode_it(0x4348).to_string(), "mul r0, r1, r0");
+        assert_eq!(decode_it(0x4288).to_string(), "cmp r0, r1");
+        assert_eq!(inst(decode_it(0x2101), Condition::Eq), "moveq r1, #1");
+        assert_eq!(inst(decode(0x1888), Condition::Lt), "addslt r0, r1, r2");
+        assert_eq!(inst(decode(0xbf00), Condition::Ne), "nopne");
+        assert_eq!(inst(decode(0xe7fe), Condition::Al), "b -4");
+    }
+
+    #[test]
+    fn multiply_divide() {
+        assert_eq!(disassemble32(0xfb01, 0xf002), "mul r0, r1, r2");
+        assert_eq!(disassemble32(0xfb01, 0x3002), "mla r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfb01, 0x3012), "mls r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfb82, 0x0103), "smull r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfba2, 0x0103), "umull r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfbc2, 0x0103), "smlal r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfbe2, 0x0103), "umlal r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfbe2, 0x0163), "umaal r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfb91, 0xf0f2), "sdiv r0, r1, r2");
+        assert_eq!(disassemble32(0xfbb1, 0xf0f2), "udiv r0, r1, r2");
+    }
+
+    #[test]
+    fn dsp_multiply() {
+        assert_eq!(disassemble32(0xfb11, 0x3012), "smlabt r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfb11, 0xf022), "smultb r0, r1, r2");
+        assert_eq!(disassemble32(0xfb31, 0x3002), "smlawb r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfb31, 0xf012), "smulwt r0, r1, r2");
+        assert_eq!(disassemble32(0xfb21, 0x3002), "smlad r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfb21, 0xf012), "smuadx r0, r1, r2");
+        assert_eq!(disassemble32(0xfb41, 0x3002), "smlsd r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfb41, 0xf002), "smusd r0, r1, r2");
+        assert_eq!(disassemble32(0xfb51, 0x3002), "smmla r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfb51, 0xf012), "smmulr r0, r1, r2");
+        assert_eq!(disassemble32(0xfb61, 0x3002), "smmls r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfbc2, 0x0193), "smlalbt r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfbc2, 0x01c3), "smlald r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xfbd2, 0x01d3), "smlsldx r0, r1, r2, r3");
+    }
 
+    #[test]
+    fn dsp_simd() {
+        assert_eq!(disassemble32(0xfa91, 0xf002), "sadd16 r0, r1, r2");
+        assert_eq!(disassemble32(0xfac1, 0xf052), "uqsub8 r0, r1, r2");
+        assert_eq!(disassemble32(0xfaa1, 0xf022), "shasx r0, r1, r2");
+        assert_eq!(disassemble32(0xfae1, 0xf062), "uhsax r0, r1, r2");
+        assert_eq!(disassemble32(0xfa82, 0xf081), "qadd r0, r1, r2");
+        assert_eq!(disassemble32(0xfa82, 0xf0b1), "qdsub r0, r1, r2");
+        assert_eq!(disassemble32(0xfaa1, 0xf082), "sel r0, r1, r2");
+        assert_eq!(disassemble32(0xf301, 0x0087), "ssat r0, #8, r1, lsl #2");
+        assert_eq!(disassemble32(0xf3a1, 0x00c7), "usat r0, #7, r1, asr #3");
+        assert_eq!(disassemble32(0xf321, 0x0007), "ssat16 r0, #8, r1");
+        assert_eq!(disassemble32(0xf3a1, 0x000f), "usat16 r0, #15, r1");
+        assert_eq!(disassemble32(0xfb71, 0xf002), "usad8 r0, r1, r2");
+        assert_eq!(disassemble32(0xfb71, 0x3002), "usada8 r0, r1, r2, r3");
+        assert_eq!(disassemble32(0xeac1, 0x1002), "pkhbt r0, r1, r2, lsl #4");
+        assert_eq!(disassemble32(0xeac1, 0x4022), "pkhtb r0, r1, r2, asr #16");
+        assert_eq!(disassemble32(0xfa21, 0xf092), "sxtab16 r0, r1, r2, ror #8");
+        assert_eq!(disassemble32(0xfa3f, 0xf082), "uxtb16 r0, r2");
+    }
+
+    #[test]
+    fn floating_point() {
+        assert_eq!(disassemble32(0xed91, 0x0a01), "vldr s0, [r1, #4]");
+        assert_eq!(disassemble32(0xed0d, 0x1b02), "vstr d1, [sp, #-8]");
+        assert_eq!(disassemble32(0xed2d, 0x8a10), "vpush {s16-s31}");
+        assert_eq!(disassemble32(0xecbd, 0x8b02), "vpop {d8}");
+        assert_eq!(disassemble32(0xecb0, 0x0a02), "vldmia r0!, {s0-s1}");
+        assert_eq!(disassemble32(0xee00, 0x1a10), "vmov s0, r1");
+        assert_eq!(disassemble32(0xee10, 0x1a10), "vmov r1, s0");
+        assert_eq!(disassemble32(0xec51, 0x0b10), "vmov r0, r1, d0");
+        assert_eq!(disassemble32(0xec41, 0x0a10), "vmov s0, s1, r0, r1");
+        assert_eq!(disassemble32(0xeeb7, 0x0a00), "vmov.f32 s0, #1.0");
+        assert_eq!(disassemble32(0xeeb0, 0x0a60), "vmov.f32 s0, s1");
+        assert_eq!(disassemble32(0xee30, 0x0a81), "vadd.f32 s0, s1, s2");
+        assert_eq!(disassemble32(0xee30, 0x0a60), "vsub.f32 s0, s0, s1");
+        assert_eq!(disassemble32(0xee20, 0x1a20), "vmul.f32 s2, s0, s1");
+        assert_eq!(disassemble32(0xee80, 0x0a20), "vdiv.f32 s0, s0, s1");
+        assert_eq!(disassemble32(0xee00, 0x0a81), "vmla.f32 s0, s1, s2");
+        assert_eq!(disassemble32(0xeea0, 0x0a81), "vfma.f32 s0, s1, s2");
+        assert_eq!(disassemble32(0xeeb1, 0x0ae0), "vsqrt.f32 s0, s1");
+        assert_eq!(disassemble32(0xeeb5, 0x0ac0), "vcmpe.f32 s0, #0.0");
+        assert_eq!(disassemble32(0xeeb4, 0x0a60), "vcmp.f32 s0, s1");
+        assert_eq!(disassemble32(0xeebd, 0x0ac0), "vcvt.s32.f32 s0, s0");
+        assert_eq!(disassemble32(0xeebd, 0x0a40), "vcvtr.s32.f32 s0, s0");
+        assert_eq!(disassemble32(0xeeb8, 0x0ac0), "vcvt.f32.s32 s0, s0");
+        assert_eq!(disassemble32(0xeeb8, 0x0a40), "vcvt.f32.u32 s0, s0");
+        assert_eq!(disassemble32(0xeebe, 0x0ac8), "vcvt.s32.f32 s0, s0, #16");
+        assert_eq!(disassemble32(0xeeb2, 0x0a60), "vcvtb.f32.f16 s0, s1");
+        assert_eq!(disassemble32(0xeeb3, 0x0ae0), "vcvtt.f16.f32 s0, s1");
+        assert_eq!(disassemble32(0xeef1, 0xfa10), "vmrs APSR_nzcv, fpscr");
+        assert_eq!(disassemble32(0xeee1, 0x0a10), "vmsr fpscr, r0");
+        assert_eq!(disassemble32(0xee30, 0x0b81), "undefined");
+    }
+
+    #[test]
+    fn modified_immediate() {
+        assert_eq!(disassemble32(0xf001, 0x20ff), "and r0, r1, #4278255360");
+        assert_eq!(disassemble32(0xf05f, 0x4000), "movs r0, #2147483648");
+        assert_eq!(disassemble32(0xf06f, 0x0000), "mvn r0, #0");
+        assert_eq!(disassemble32(0xf090, 0x0f01), "teq r0, #1");
+        assert_eq!(Thumb32::expand_imm(0x0ab), (0x000000ab, None));
+        assert_eq!(Thumb32::expand_imm(0x1ab), (0x00ab00ab, None));
+        assert_eq!(Thumb32::expand_imm(0x2ab), (0xab00ab00, None));
+        assert_eq!(Thumb32::expand_imm(0x3ab), (0xabababab, None));
+        assert_eq!(Thumb32::expand_imm(0x400), (0x80000000, Some(true)));
+        assert_eq!(Thumb32::expand_imm(0xfff), (0x000001fe, Some(false)));
+    }
+
+    #[test]
+    fn plain_immediate() {
+        assert_eq!(disassemble32(0xf601, 0x70ff), "add r0, r1, #4095");
+        assert_eq!(disassemble32(0xf241, 0x2034), "mov r0, #4660");
+        assert_eq!(disassemble32(0xf2c1, 0x2034), "movt r0, #4660");
+        assert_eq!(disassemble32(0xf2af, 0x0008), "adr r0, #-8");
+    }
+
+    #[test]
+    fn shifted_register() {
+        assert_eq!(disassemble32(0xeb01, 0x00c2), "add r0, r1, r2, lsl #3");
+        assert_eq!(disassemble32(0xea61, 0x0002), "orn r0, r1, r2");
+        assert_eq!(disassemble32(0xebb0, 0x1f11), "cmp r0, r1, lsr #4");
+        assert_eq!(disassemble32(0xea4f, 0x0031), "rrx r0, r1");
+        assert_eq!(disassemble32(0xfa01, 0xf002), "lsl r0, r1, r2");
+    }
+
+    #[test]
+    fn wide_branches() {
+        assert_eq!(disassemble32(0xf7ff, 0xfffe), "bl -4");
+        assert_eq!(disassemble32(0xf000, 0x8080), "beq 256");
+        assert_eq!(disassemble32(0xf7ff, 0xbc00), "b -2048");
+        assert_eq!(disassemble32(0xf3af, 0x8000), "nop");
+        assert_eq!(disassemble32(0xf3af, 0x8003), "wfi");
+        assert_eq!(disassemble32(0xf7f0, 0xa000), "undefined");
+    }
+
+    #[test]
+    fn wide_load_store() {
+        assert_eq!(disassemble32(0xf8d1, 0x0fff), "ldr r0, [r1, #4095]");
+        assert_eq!(disassemble32(0xf851, 0x0c04), "ldr r0, [r1, #-4]");
+        assert_eq!(disassemble32(0xf851, 0x0f04), "ldr r0, [r1, #4]!");
+        assert_eq!(disassemble32(0xf851, 0x0b04), "ldr r0, [r1], #4");
+        assert_eq!(disassemble32(0xf851, 0x0e04), "ldr r0, [r1, #4]");
+        assert_eq!(disassemble32(0xf851, 0x0022), "ldr r0, [r1, r2, lsl #2]");
+        assert_eq!(disassemble32(0xf9b1, 0x0002), "ldrsh r0, [r1, #2]");
+        assert_eq!(disassemble32(0xf911, 0x0c01), "ldrsb r0, [r1, #-1]");
+        assert_eq!(disassemble32(0xf85f, 0x0008), "ldr r0, [pc, #-8]");
+        assert_eq!(disassemble32(0xf881, 0x0001), "strb r0, [r1, #1]");
+        assert_eq!(disassemble32(0xf821, 0x0902), "strh r0, [r1], #-2");
+        assert_eq!(disassemble32(0xf841, 0x0012), "str r0, [r1, r2, lsl #1]");
+        assert_eq!(disassemble32(0xf890, 0xf004), "pld [r0, #4]");
+    }
+
+    #[test]
+    fn load_store_dual_table_branch() {
+        assert_eq!(disassemble32(0xe9d2, 0x0102), "ldrd r0, r1, [r2, #8]");
+        assert_eq!(disassemble32(0xe962, 0x0102), "strd r0, r1, [r2, #-8]!");
+        assert_eq!(disassemble32(0xe9df, 0x0102), "ldrd r0, r1, [pc, #8]");
+        assert_eq!(disassemble32(0xe8d0, 0xf001), "tbb [r0, r1]");
+        assert_eq!(disassemble32(0xe8df, 0xf011), "tbh [pc, r1, lsl #1]");
+    }
+
+    #[test]
+    fn bit_manipulation() {
+        assert_eq!(disassemble(0xb208), "sxth r0, r1");
+        assert_eq!(disassemble(0xb248), "sxtb r0, r1");
+        assert_eq!(disassemble(0xb288), "uxth r0, r1");
+        assert_eq!(disassemble(0xb2c8), "uxtb r0, r1");
+        assert_eq!(disassemble(0xba08), "rev r0, r1");
+        assert_eq!(disassemble(0xba48), "rev16 r0, r1");
+        assert_eq!(disassemble(0xbac8), "revsh r0, r1");
+        assert_eq!(disassemble32(0xfa0f, 0xf091), "sxth r0, r1, ror #8");
+        assert_eq!(disassemble32(0xfa5f, 0xf081), "uxtb r0, r1");
+        assert_eq!(disassemble32(0xfa51, 0xf0a2), "uxtab r0, r1, r2, ror #16");
+        assert_eq!(disassemble32(0xfab1, 0xf081), "clz r0, r1");
+        assert_eq!(disassemble32(0xfa91, 0xf0a1), "rbit r0, r1");
+        assert_eq!(disassemble32(0xfa91, 0xf081), "rev r0, r1");
+        assert_eq!(disassemble32(0xf361, 0x100b), "bfi r0, r1, #4, #8");
+        assert_eq!(disassemble32(0xf36f, 0x100b), "bfc r0, #4, #8");
+        assert_eq!(disassemble32(0xf3c1, 0x1007), "ubfx r0, r1, #4, #8");
+        assert_eq!(disassemble32(0xf341, 0x1007), "sbfx r0, r1, #4, #8");
+    }
+
+    #[test]
+    fn special_registers() {
+        assert_eq!(disassemble32(0xf3ef, 0x8010), "mrs r0, PRIMASK");
+        assert_eq!(disassemble32(0xf3ef, 0x8314), "mrs r3, CONTROL");
+        assert_eq!(disassemble32(0xf3ef, 0x8003), "mrs r0, XPSR");
+        assert_eq!(disassemble32(0xf380, 0x8810), "msr PRIMASK, r0");
+        assert_eq!(disassemble32(0xf381, 0x8812), "msr BASEPRI_MAX, r1");
+        assert_eq!(disassemble32(0xf380, 0x8800), "msr APSR_nzcvq, r0");
+        assert_eq!(disassemble32(0xf380, 0x8c00), "msr APSR_nzcvqg, r0");
+        assert_eq!(disassemble(0xb662), "cpsie i");
+        assert_eq!(disassemble(0xb672), "cpsid i");
+        assert_eq!(disassemble(0xb671), "cpsid f");
+    }
+
+    #[test]
+    fn exclusive_and_barriers() {
+        assert_eq!(disassemble32(0xe851, 0x0f01), "ldrex r0, [r1, #4]");
+        assert_eq!(disassemble32(0xe841, 0x3200), "strex r2, r3, [r1]");
+        assert_eq!(disassemble32(0xe8d1, 0x0f4f), "ldrexb r0, [r1]");
+        assert_eq!(disassemble32(0xe8c1, 0x0f52), "strexh r2, r0, [r1]");
+        assert_eq!(disassemble32(0xf3bf, 0x8f2f), "clrex");
+        assert_eq!(disassemble32(0xf3bf, 0x8f4f), "dsb sy");
+        assert_eq!(disassemble32(0xf3bf, 0x8f5f), "dmb sy");
+        assert_eq!(disassemble32(0xf3bf, 0x8f6f), "isb sy");
+    }
+
+    #[test]
+    fn wide_load_store_multiple() {
+        assert_eq!(disassemble32(0xe920, 0x0006), "stmdb r0!, {r1, r2}");
+        assert_eq!(disassemble32(0xe92d, 0x41f0), "push {r4, r5, r6, r7, r8, lr}");
+        assert_eq!(disassemble32(0xe8bd, 0x81f0), "pop {r4, r5, r6, r7, r8, pc}");
+        assert_eq!(disassemble32(0xe910, 0x0006), "ldmdb r0, {r1, r2}");
+        assert_eq!(disassemble32(0xe8b0, 0x0006), "ldm r0!, {r1, r2}");
+        assert_eq!(disassemble32(0xe880, 0x0006), "stm r0, {r1, r2}");
+    }
+}
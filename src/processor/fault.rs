@@ -1,16 +1,35 @@
-use super::{Processor, Mode};
+use super::{Processor, Mode, Sleep, PRIORITY_MASK};
 use super::decoder::BitVec;
+use super::scb::Scb;
 
 use crate::bus::DataBus;
 
 use std::collections::HashMap;
 
 
+// the nrf52833 wires up 48 peripheral interrupts
+pub const IRQ_COUNT: usize = 48;
+
+pub const LOCKUP_ADDRESS: u32 = 0xfffffffe;
+
+// exception latencies of the cortex-m4 without floating point context
+pub const ENTRY_CYCLES: u64 = 12;
+pub const EXIT_CYCLES: u64 = 10;
+pub const TAIL_CHAIN_CYCLES: u64 = 6;
+
 pub struct Frame {
     align: bool,
     ptr: u32,
+    extended: bool,
+}
+
+impl Frame {
+    pub fn size(&self) -> u32 {
+        if self.extended { 0x68 } else { 0x20 }
+    }
 }
 
+#[allow(dead_code)]
 #[repr(usize)]
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Exception {
@@ -29,9 +48,54 @@ pub enum Exception {
     },
 }
 
-impl Into<usize> for Exception {
-    fn into(self) -> usize {
+// page 612 @ ARMv7M Reference Manual
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fault {
+    InstructionBus,
+    PreciseData {
+        addr: u32,
+    },
+    Unstacking,
+    Stacking,
+    LazyStacking,
+    Undefined,
+    InvalidState,
+    InvalidPc,
+    NoCoprocessor,
+    Unaligned,
+    DivideByZero,
+}
+
+impl Fault {
+    pub fn exception(&self) -> Exception {
         match self {
+            Fault::InstructionBus | Fault::PreciseData { .. } | Fault::Unstacking | Fault::Stacking | Fault::LazyStacking => Exception::BusFault,
+            _ => Exception::UsageFault,
+        }
+    }
+
+    // the bit recorded in the configurable fault status register
+    pub fn status(&self) -> u32 {
+        match self {
+            Fault::InstructionBus => 1 << 8,
+            Fault::PreciseData { .. } => 1 << 9,
+            Fault::Unstacking => 1 << 11,
+            Fault::Stacking => 1 << 12,
+            Fault::LazyStacking => 1 << 13,
+            Fault::Undefined => 1 << 16,
+            Fault::InvalidState => 1 << 17,
+            Fault::InvalidPc => 1 << 18,
+            Fault::NoCoprocessor => 1 << 19,
+            Fault::Unaligned => 1 << 24,
+            Fault::DivideByZero => 1 << 25,
+        }
+    }
+}
+
+impl From<Exception> for usize {
+    fn from(exception: Exception) -> usize {
+        match exception {
             Exception::Reset => 1,
             Exception::Nmi => 2,
             Exception::HardFault => 3,
@@ -55,12 +119,16 @@ pub struct Priority {
 impl Priority {
     pub fn new(priorities: Vec<(Exception, i32)>) -> Priority {
         Priority {
-            priorities: priorities.iter().map(|x| *x).collect::<HashMap<Exception, i32>>(),
+            priorities: priorities.iter().copied().collect::<HashMap<Exception, i32>>(),
         }
     }
 
     pub fn get(&self, exception: Exception) -> i32 {
-        self.priorities.get(&exception).map(|x| *x).unwrap_or(0)
+        self.priorities.get(&exception).copied().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, exception: Exception, priority: i32) {
+        self.priorities.insert(exception, priority);
     }
 }
 
@@ -68,7 +136,11 @@ impl Priority {
 pub struct InterruptController {
     priority: Priority,
     pending: Vec<Exception>,
-    group: i32,
+    active: Vec<Exception>,
+    enabled: u64,
+    pub scb: Scb,
+    // the event register that wfe waits on
+    pub event: bool,
 }
 
 impl InterruptController {
@@ -82,17 +154,44 @@ impl InterruptController {
         InterruptController {
             priority: Priority::new(priorities),
             pending: Vec::new(),
-            group: 1,
+            active: Vec::new(),
+            enabled: 0,
+            scb: Scb::new(),
+            event: false,
+        }
+    }
+
+    pub fn priority(&self, exception: Exception) -> i32 {
+        self.priority.get(exception)
+    }
+
+    pub fn set_priority(&mut self, exception: Exception, priority: i32) {
+        self.priority.set(exception, priority);
+    }
+
+    // system exceptions have no enable bit in the nvic
+    fn enabled(&self, exception: Exception) -> bool {
+        match exception {
+            Exception::Interrupt { offset } => self.enabled & (1 << (offset - 16)) != 0,
+            _ => true,
         }
     }
 
     pub fn throw(&mut self, exception: Exception) {
-        if self.priority.get(exception) < self.group {
+        if !self.pending.contains(&exception) {
             self.pending.push(exception);
+
+            self.event |= self.scb.sev_on_pend();
         }
     }
 
-    pub fn poll(&mut self) -> Option<Exception> {
+    // the thread mode base level of 256 is lower than any configurable priority
+    pub fn active_priority(&self) -> i32 {
+        self.active.iter().map(|exception| self.scb.group(self.priority.get(*exception))).min().unwrap_or(256)
+    }
+
+    // takes the most urgent pending exception if it can preempt the given execution priority
+    pub fn poll(&mut self, priority: i32) -> Option<Exception> {
         self.pending.sort_by(|a, b| {
             if self.priority.get(*a) == self.priority.get(*b) {
                 Into::<usize>::into(*b).cmp(&Into::<usize>::into(*a))
@@ -101,55 +200,322 @@ impl InterruptController {
             }
         });
 
-        self.pending.pop()
+        match self.pending.iter().rposition(|exception| self.enabled(*exception)) {
+            Some(index) if self.scb.group(self.priority.get(self.pending[index])) < priority => {
+                let exception = self.pending.remove(index);
+
+                self.active.push(exception);
+
+                Some(exception)
+            },
+            _ => None,
+        }
+    }
+
+    // each bank register holds one bit for 32 interrupts, the offset is relative to 0xe000e100
+    fn bank(&self, offset: usize, f: impl Fn(Exception) -> bool) -> u32 {
+        (0..32)
+            .filter(|bit| (offset & 0x7c) * 8 + bit < IRQ_COUNT)
+            .filter(|bit| f(Exception::Interrupt { offset: 16 + (offset & 0x7c) * 8 + bit }))
+            .fold(0, |bank, bit| bank | (1 << bit))
+    }
+
+    // page 626 @ ARMv7M Reference Manual
+    pub fn read_register(&self, offset: usize) -> u32 {
+        match offset {
+            0x000..0x100 => self.bank(offset, |exception| self.enabled(exception)),
+            0x100..0x200 => self.bank(offset, |exception| self.pending.contains(&exception)),
+            0x200..0x280 => self.bank(offset, |exception| self.active.contains(&exception)),
+            0x300..0x4f0 => (0..4)
+                .filter(|lane| offset - 0x300 + lane < IRQ_COUNT)
+                .fold(0, |value, lane| value | (self.priority.get(Exception::Interrupt { offset: 16 + offset - 0x300 + lane }) as u32) << (lane * 8)),
+            _ => 0,
+        }
+    }
+
+    pub fn write_register(&mut self, offset: usize, value: u32, mask: u32) {
+        let irqs = (0..32)
+            .filter(|bit| value & mask & (1 << bit) != 0)
+            .map(|bit| (offset & 0x7c) * 8 + bit)
+            .filter(|irq| *irq < IRQ_COUNT);
+
+        match offset {
+            0x000..0x080 => irqs.for_each(|irq| self.enabled |= 1 << irq),
+            0x080..0x100 => irqs.for_each(|irq| self.enabled &= !(1 << irq)),
+            0x100..0x180 => irqs.for_each(|irq| self.throw(Exception::Interrupt { offset: 16 + irq })),
+            0x180..0x200 => irqs.for_each(|irq| self.clear(Exception::Interrupt { offset: 16 + irq })),
+            0x300..0x4f0 => {
+                for lane in (0..4).filter(|lane| mask & (0xff << (lane * 8)) != 0 && offset - 0x300 + lane < IRQ_COUNT) {
+                    let priority = (value >> (lane * 8)) as u8 & PRIORITY_MASK;
+
+                    self.set_priority(Exception::Interrupt { offset: 16 + offset - 0x300 + lane }, priority as i32);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn clear(&mut self, exception: Exception) {
+        self.pending.retain(|pending| *pending != exception);
+    }
+
+    fn system_handler(number: usize) -> Option<Exception> {
+        match number {
+            4 => Some(Exception::MemManage),
+            5 => Some(Exception::BusFault),
+            6 => Some(Exception::UsageFault),
+            11 => Some(Exception::SVCall),
+            12 => Some(Exception::DebugMonitor),
+            14 => Some(Exception::PendSV),
+            15 => Some(Exception::SysTick),
+            _ => None,
+        }
+    }
+
+    // page 600 @ ARMv7M Reference Manual
+    fn icsr(&self) -> u32 {
+        let vectactive = self.active.last().map(|exception| Into::<usize>::into(*exception)).unwrap_or(0) as u32;
+        let vectpending = self.pending.iter()
+            .filter(|exception| self.enabled(**exception))
+            .min_by_key(|exception| (self.priority.get(**exception), Into::<usize>::into(**exception)))
+            .map(|exception| Into::<usize>::into(*exception))
+            .unwrap_or(0) as u32;
+
+        let isrpending = self.pending.iter().any(|exception| matches!(exception, Exception::Interrupt { .. }));
+        let rettobase = self.active.len() <= 1;
+
+        ((self.pending.contains(&Exception::Nmi) as u32) << 31)
+            | ((self.pending.contains(&Exception::PendSV) as u32) << 28)
+            | ((self.pending.contains(&Exception::SysTick) as u32) << 26)
+            | ((isrpending as u32) << 22)
+            | (vectpending << 12)
+            | ((rettobase as u32) << 11)
+            | vectactive
+    }
+
+    // the offset is relative to 0xe000ed00, returns whether a system reset was requested
+    pub fn write_system_register(&mut self, offset: usize, value: u32, mask: u32) -> bool {
+        match offset {
+            0x04 => {
+                let value = value & mask;
+
+                if value & (1 << 31) != 0 { self.throw(Exception::Nmi) }
+                if value & (1 << 28) != 0 { self.throw(Exception::PendSV) }
+                if value & (1 << 27) != 0 { self.clear(Exception::PendSV) }
+                if value & (1 << 26) != 0 { self.throw(Exception::SysTick) }
+                if value & (1 << 25) != 0 { self.clear(Exception::SysTick) }
+
+                false
+            },
+            // page 608 @ ARMv7M Reference Manual
+            0x18..0x24 => {
+                let lanes = (0..4).filter(|lane| mask & (0xff << (lane * 8)) != 0);
+
+                for (exception, lane) in lanes.filter_map(|lane| InterruptController::system_handler(offset - 0x14 + lane).map(|exception| (exception, lane))) {
+                    self.set_priority(exception, ((value >> (lane * 8)) as u8 & PRIORITY_MASK) as i32);
+                }
+
+                false
+            },
+            _ => self.scb.write_register(offset, value, mask),
+        }
+    }
+
+    // page 609 @ ARMv7M Reference Manual
+    fn shcsr(&self) -> u32 {
+        // the active bit and, where the register has one, the pended bit of each system handler
+        let status = [
+            (Exception::MemManage, 1 << 0, Some(1 << 13)),
+            (Exception::BusFault, 1 << 1, Some(1 << 14)),
+            (Exception::UsageFault, 1 << 3, Some(1 << 12)),
+            (Exception::SVCall, 1 << 7, Some(1 << 15)),
+            (Exception::DebugMonitor, 1 << 8, None),
+            (Exception::PendSV, 1 << 10, None),
+            (Exception::SysTick, 1 << 11, None),
+        ];
+
+        status.iter().fold(self.scb.read_register(0x24), |mut value, (exception, active, pended)| {
+            if self.active.contains(exception) {
+                value |= active;
+            }
+
+            if let Some(pended) = pended.filter(|_| self.pending.contains(exception)) {
+                value |= pended;
+            }
+
+            value
+        })
+    }
+
+    pub fn read_system_register(&self, offset: usize) -> u32 {
+        match offset {
+            0x04 => self.icsr(),
+            0x24 => self.shcsr(),
+            0x18..0x24 => (0..4)
+                .filter_map(|lane| InterruptController::system_handler(offset - 0x14 + lane).map(|exception| (exception, lane)))
+                .fold(0, |value, (exception, lane)| value | (self.priority.get(exception) as u32) << (lane * 8)),
+            _ => self.scb.read_register(offset),
+        }
+    }
+
+    pub fn is_active(&self, number: usize) -> bool {
+        self.active.iter().any(|exception| Into::<usize>::into(*exception) == number)
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.active.len()
+    }
+
+    pub fn deactivate(&mut self, number: usize) {
+        self.active.retain(|exception| Into::<usize>::into(*exception) != number);
+    }
+
+    // hands an exception that lost its entry to a late arrival back to the pending set
+    pub fn repend(&mut self, exception: Exception) {
+        self.deactivate(exception.into());
+
+        self.throw(exception);
     }
 }
 
 impl Processor {
+    // synchronous exceptions that are disabled or cannot preempt escalate to hardfault, page 532 @ ARMv7M Reference Manual
+    pub fn raise(&mut self, exception: Exception) {
+        let priority = self.nvic.scb.group(self.nvic.priority(exception));
+
+        let exception = if exception != Exception::HardFault && (!self.nvic.scb.enabled(exception) || priority >= self.execution_priority()) {
+            self.nvic.scb.hfsr |= 1 << 30;
+
+            Exception::HardFault
+        } else {
+            exception
+        };
+
+        // a hardfault that cannot preempt locks the processor up instead, page 535 @ ARMv7M Reference Manual
+        if exception == Exception::HardFault && self.nvic.priority(Exception::HardFault) >= self.execution_priority() {
+            self.lockup();
+        } else {
+            self.nvic.throw(exception);
+        }
+    }
+
+    pub fn lockup(&mut self) {
+        self.lockup = true;
+        self.branch = true;
+
+        self.registers.set(15, |_| LOCKUP_ADDRESS, self.mode);
+    }
+
+    pub fn locked_up(&self) -> bool {
+        self.lockup
+    }
+
+    pub fn record_fault(&mut self, fault: Fault) {
+        self.nvic.scb.record(fault);
+
+        self.raise(fault.exception());
+    }
+
+    // page 532 @ ARMv7M Reference Manual
+    pub fn execution_priority(&self) -> i32 {
+        let boosted = if self.registers.faultmask {
+            -1
+        } else if self.registers.primask {
+            0
+        } else if self.registers.basepri != 0 {
+            self.nvic.scb.group(self.registers.basepri as i32)
+        } else {
+            256
+        };
+
+        boosted.min(self.nvic.active_priority())
+    }
+
     pub fn frame(&mut self) -> Frame {
-        let align = (self.registers.get(13, self.mode) & (1 << 2)) != 0;
+        // without stkalign the frame only keeps the architectural word alignment
+        let stkalign = self.nvic.scb.ccr.stkalign();
 
-        self.registers.set(13, |sp| (sp - 0x20) & ((1 << 2) ^ 0xFFFF_FFFF), self.mode);
+        let align = stkalign && (self.registers.get(13, self.mode) & (1 << 2)) != 0;
+        let extended = self.registers.control.fpca;
+        let size = if extended { 0x68 } else { 0x20 };
+
+        self.registers.set(13, |sp| sp.wrapping_sub(size) & (((stkalign as u32) << 2) ^ 0xFFFF_FFFF), self.mode);
 
         Frame {
             align,
             ptr: self.registers.get(13, self.mode),
+            extended,
         }
     }
 
     pub fn push_stack(&mut self) {
         let frame = self.frame();
 
-        for (offset, register) in [0, 1, 2, 3, 12, 14, 15].iter().enumerate() {
-            self.write::<u32>(frame.ptr as usize + (offset * 4), self.registers.get(*register, self.mode));
+        for (offset, register) in [0, 1, 2, 3, 12, 14].iter().enumerate() {
+            self.write::<u32>(frame.ptr.wrapping_add(offset as u32 * 4) as usize, self.registers.get(*register, self.mode));
         }
 
-        self.write::<u32>(frame.ptr as usize + 0x1c, (self.registers.psr.value & !(1 << 9)) | ((frame.align as u32) << 9));
+        self.write::<u32>(frame.ptr.wrapping_add(0x18) as usize, self.registers.pc());
+
+        self.write::<u32>(frame.ptr.wrapping_add(0x1c) as usize, (self.registers.psr.value & !(1 << 9)) | ((frame.align as u32) << 9));
+
+        // page 536 @ ARMv7M Reference Manual
+        if frame.extended {
+            if self.registers.fpccr.lspen() {
+                self.registers.fpcar = frame.ptr.wrapping_add(0x20);
+
+                self.registers.fpccr.defer(self.mode == Mode::Thread && self.registers.control.private, self.mode == Mode::Thread);
+            } else {
+                self.preserve_fp(frame.ptr.wrapping_add(0x20));
+            }
+        }
+
+        // bit 4 of exc_return is clear when the frame holds the floating point context
+        let extended = if frame.extended { 0 } else { 1 << 4 };
 
         if self.mode == Mode::Handle {
-            self.registers.set(14, |_| 0xfffffff1, self.mode);
+            self.registers.set(14, |_| 0xffffffe1 | extended, self.mode);
         } else if !self.registers.control.stack {
-            self.registers.set(14, |_| 0xfffffff9, self.mode);
+            self.registers.set(14, |_| 0xffffffe9 | extended, self.mode);
         } else {
-            self.registers.set(14, |_| 0xfffffffd, self.mode);
+            self.registers.set(14, |_| 0xffffffed | extended, self.mode);
         }
     }
 
     pub fn pop_stack(&mut self, frame: Frame, exc_return: u32) {
         for (offset, register) in [0, 1, 2, 3, 12, 14, 15].iter().enumerate() {
-            let value = self.read::<u32>(frame.ptr as usize + (offset * 4));
+            let value = self.read::<u32>(frame.ptr.wrapping_add(offset as u32 * 4) as usize);
 
             self.registers.set(*register, |_| value, self.mode);
         }
 
-        self.registers.psr.value = self.read::<u32>(frame.ptr as usize + 0x1c);
+        // page 541 @ ARMv7M Reference Manual
+        if frame.extended {
+            if self.registers.fpccr.lspact() {
+                self.registers.fpccr.set_lspact(false);
+            } else {
+                self.restore_fp(frame.ptr.wrapping_add(0x20));
+            }
+        }
+
+        self.registers.control.fpca = frame.extended;
+
+        self.registers.psr.value = self.read::<u32>(frame.ptr.wrapping_add(0x1c) as usize);
+
+        if self.bus_error.take().is_some() {
+            self.record_fault(Fault::Unstacking);
+        }
+
+        let align = (self.registers.psr.get(9) as u32) << 2;
+
+        self.registers.psr.value &= !(1 << 9);
 
         match exc_return.get(0..4) {
             0b0001 | 0b1001 => {
-                self.registers.sp.msp = (self.registers.sp.msp + 0x20) | (self.registers.psr.get(9) as u32) << 2;
+                self.registers.sp.msp = self.registers.sp.msp.wrapping_add(frame.size()) | align;
             },
             0b1101 => {
-                self.registers.sp.psp = (self.registers.sp.psp + 0x20) | (self.registers.psr.get(9) as u32) << 2;
+                self.registers.sp.psp = self.registers.sp.psp.wrapping_add(frame.size()) | align;
             },
             _ => {},
         }
@@ -162,40 +528,113 @@ impl Processor {
                 self.mode = Mode::Handle;
 
                 self.registers.control.stack = false;
+                self.registers.control.fpca = false;
+
+                self.exclusive = None;
+
+                // the interrupted it state lives on in the stacked xpsr until exception return
+                self.registers.psr.set_it_state(0);
 
-                self.registers.psr.value |= Into::<usize>::into(exception) as u32 & 0xff;
+                self.registers.psr.value = (self.registers.psr.value & !0x1ff) | (Into::<usize>::into(exception) as u32 & 0x1ff);
 
                 let handler = self.read::<u32>(self.registers.vtor.addr() as usize + Into::<usize>::into(exception) * 4);
 
-                self.registers.set(15, |_| handler, self.mode);
+                if self.bus_error.take().is_some() {
+                    self.nvic.scb.hfsr |= 1 << 1;
+
+                    self.raise(Exception::HardFault);
+                }
+
+                if !self.lockup {
+                    self.registers.set(15, |_| handler, self.mode);
 
-                if (handler & 1) != 0 {
-                    self.registers.psr.set(24);
+                    self.registers.psr.write(24, handler & 1 != 0);
                 }
             },
         }
     }
 
     pub fn exception_return(&mut self, exc_return: u32) {
-        match exc_return.get(0..4) {
-            0b0001 | 0b1001 => {
-                self.mode = exc_return.get(0..4)
-                    .eq(&0b0001)
-                    .then(|| Mode::Handle)
-                    .unwrap_or(Mode::Thread);
+        self.exclusive = None;
 
-                self.registers.control.stack = false;
+        let number = (self.registers.psr.value & 0x1ff) as usize;
 
-                self.pop_stack(Frame {
-                    align: false,
-                    ptr: self.registers.sp.msp,
-                }, exc_return);
-            },
-            0b1101 => {
-            },
-            _ => {
-                self.nvic.throw(Exception::UsageFault);
-            },
+        let active = self.nvic.is_active(number);
+        let nested = self.nvic.active_count();
+
+        // page 541 @ ARMv7M Reference Manual
+        if number != Into::<usize>::into(Exception::Nmi) {
+            self.registers.faultmask = false;
+        }
+
+        self.nvic.deactivate(number);
+
+        // returning to thread mode with other exceptions still active needs ccr.nonbasethrdena
+        let thread = nested == 1 || self.nvic.scb.ccr.nonbasethrdena();
+
+        let target = match exc_return.get(0..4) {
+            _ if !active || exc_return & 0x0fffffe0 != 0x0fffffe0 => None,
+            0b0001 if nested > 1 => Some((Mode::Handle, false)),
+            0b1001 if thread => Some((Mode::Thread, false)),
+            0b1101 if thread => Some((Mode::Thread, true)),
+            _ => None,
+        };
+
+        let Some((mode, stack)) = target else {
+            self.registers.set(14, |_| 0xf0000000 | exc_return, self.mode);
+
+            self.record_fault(Fault::InvalidPc);
+
+            return;
+        };
+
+        // a pending exception that can preempt the returning context reuses the frame instead of unstacking
+        if let Some(exception) = self.nvic.poll(self.execution_priority()) {
+            self.registers.set(14, |_| 0xf0000000 | exc_return, self.mode);
+
+            self.exception_entry(exception);
+
+            self.cycles += TAIL_CHAIN_CYCLES;
+
+            return;
+        }
+
+        // with scr.sleeponexit the frame stays stacked and the next exception is taken as if it was tail-chained
+        if mode == Mode::Thread && self.nvic.scb.sleep_on_exit() {
+            self.sleep = Some(Sleep::Exit(exc_return));
+
+            return;
+        }
+
+        self.cycles += EXIT_CYCLES;
+
+        self.mode = mode;
+
+        self.registers.control.stack = stack;
+
+        let ptr = if stack { self.registers.sp.psp } else { self.registers.sp.msp };
+
+        self.pop_stack(Frame {
+            align: false,
+            ptr,
+            extended: exc_return & (1 << 4) == 0,
+        }, exc_return);
+
+        // page 541 @ ARMv7M Reference Manual
+        if (mode == Mode::Thread) != (self.registers.psr.value & 0x1ff == 0) {
+            // the pop is undone and the usagefault is taken on top of the frame that is still stacked
+            match stack {
+                true => self.registers.sp.psp = ptr,
+                false => self.registers.sp.msp = ptr,
+            }
+
+            self.registers.set(14, |_| 0xf0000000 | exc_return, self.mode);
+
+            self.record_fault(Fault::InvalidPc);
+
+            if let Some(exception) = self.nvic.poll(self.execution_priority()) {
+                self.exception_entry(exception);
+            }
         }
     }
 }
@@ -215,12 +654,58 @@ mod tests {
         nvic.throw(Exception::SysTick);
         nvic.throw(Exception::MemManage);
 
-        assert_eq!(Some(Exception::Reset), nvic.poll());
-        assert_eq!(Some(Exception::Nmi), nvic.poll());
-        assert_eq!(Some(Exception::HardFault), nvic.poll());
-        assert_eq!(Some(Exception::MemManage), nvic.poll());
-        assert_eq!(Some(Exception::BusFault), nvic.poll());
-        assert_eq!(Some(Exception::SysTick), nvic.poll());
+        assert_eq!(Some(Exception::Reset), nvic.poll(256));
+        assert_eq!(Some(Exception::Nmi), nvic.poll(256));
+        assert_eq!(Some(Exception::HardFault), nvic.poll(256));
+        assert_eq!(Some(Exception::MemManage), nvic.poll(256));
+        assert_eq!(Some(Exception::BusFault), nvic.poll(256));
+        assert_eq!(Some(Exception::SysTick), nvic.poll(256));
+    }
+
+    #[test]
+    fn nvic_registers() {
+        let mut nvic = InterruptController::new();
+
+        nvic.write_register(0x000, 1 << 6, u32::MAX);
+        nvic.write_register(0x004, 0xffffffff, u32::MAX);
+        nvic.write_register(0x084, 1 << 1, u32::MAX);
+
+        assert_eq!(nvic.read_register(0x000), 1 << 6);
+        assert_eq!(nvic.read_register(0x084), 0x0000fffd);
+
+        nvic.write_register(0x100, (1 << 6) | (1 << 7), u32::MAX);
+        nvic.write_register(0x180, 1 << 7, u32::MAX);
+
+        assert_eq!(nvic.read_register(0x100), 1 << 6);
+
+        nvic.write_register(0x304, 0xff << 16, 0xff << 16);
+
+        assert_eq!(nvic.read_register(0x304), 0xe0 << 16);
+        assert_eq!(nvic.read_register(0x330), 0);
+
+        assert_eq!(Some(Exception::Interrupt { offset: 22 }), nvic.poll(256));
+        assert_eq!(nvic.read_register(0x200), 1 << 6);
+        assert_eq!(nvic.active_priority(), 0xe0);
+    }
+
+    #[test]
+    fn pending_until_unmasked() {
+        let mut nvic = InterruptController::new();
+
+        nvic.set_priority(Exception::SysTick, 0x40);
+        nvic.throw(Exception::SysTick);
+        nvic.throw(Exception::SysTick);
+        nvic.throw(Exception::SVCall);
+
+        assert_eq!(None, nvic.poll(0));
+        assert_eq!(Some(Exception::SVCall), nvic.poll(256));
+        assert_eq!(0, nvic.active_priority());
+        assert_eq!(None, nvic.poll(nvic.active_priority()));
+
+        nvic.deactivate(11);
+
+        assert_eq!(Some(Exception::SysTick), nvic.poll(256));
+        assert_eq!(None, nvic.poll(256));
     }
 }
 
@@ -0,0 +1,823 @@
+use super::Processor;
+use super::instruction::{InstructionKind, FpRegister, VfpOp, VfpUnaryOp};
+use super::registers::{Fpscr, FpException, Rounding, Flag};
+use super::fault::Fault;
+
+use crate::bus::DataBus;
+
+use std::cmp::Ordering;
+
+
+const DEFAULT_NAN: u32 = 0x7fc00000;
+
+fn is_nan(x: u32) -> bool {
+    f32::from_bits(x).is_nan()
+}
+
+fn is_signalling(x: u32) -> bool {
+    is_nan(x) && x & (1 << 22) == 0
+}
+
+fn negate(x: u32) -> u32 {
+    x ^ (1 << 31)
+}
+
+fn step_up(x: f32) -> f32 {
+    if x == 0.0 {
+        f32::from_bits(1)
+    } else if x > 0.0 {
+        f32::from_bits(x.to_bits() + 1)
+    } else {
+        f32::from_bits(x.to_bits() - 1)
+    }
+}
+
+fn step_down(x: f32) -> f32 {
+    -step_up(-x)
+}
+
+fn signed_zero(negative: bool) -> u32 {
+    (negative as u32) << 31
+}
+
+// page 88 @ ARMv7M Reference Manual
+fn process_nans(fpscr: &mut Fpscr, operands: &[u32]) -> Option<u32> {
+    let nan = operands.iter().find(|x| is_signalling(**x)).or(operands.iter().find(|x| is_nan(**x)))?;
+
+    if operands.iter().any(|x| is_signalling(*x)) {
+        fpscr.raise(FpException::InvalidOperation);
+    }
+
+    Some(if fpscr.default_nan() { DEFAULT_NAN } else { nan | (1 << 22) })
+}
+
+fn invalid(fpscr: &mut Fpscr) -> u32 {
+    fpscr.raise(FpException::InvalidOperation);
+
+    DEFAULT_NAN
+}
+
+// denormal inputs are flushed to zero when FPSCR.FZ is set, page 84 @ ARMv7M Reference Manual
+fn unpack(fpscr: &mut Fpscr, x: u32) -> f64 {
+    let value = f32::from_bits(x);
+
+    if fpscr.flush_to_zero() && value.is_subnormal() {
+        fpscr.raise(FpException::InputDenormal);
+
+        return if value.is_sign_negative() { -0.0 } else { 0.0 };
+    }
+
+    value as f64
+}
+
+// rounds value + residual to single precision, where residual is the error left over by the f64 computation
+fn round(fpscr: &mut Fpscr, value: f64, residual: f64, rounding: Rounding) -> u32 {
+    if !value.is_finite() {
+        return (value as f32).to_bits();
+    }
+
+    if fpscr.flush_to_zero() && value != 0.0 && value.abs() < f32::MIN_POSITIVE as f64 {
+        fpscr.raise(FpException::Underflow);
+
+        return signed_zero(value.is_sign_negative());
+    }
+
+    let nearest = value as f32;
+    let difference = value - nearest as f64;
+
+    // whether the exact result lies above or below the nearest single
+    let order = match difference == 0.0 {
+        true => residual.partial_cmp(&0.0).unwrap_or(Ordering::Equal),
+        false => difference.partial_cmp(&0.0).unwrap_or(Ordering::Equal),
+    };
+
+    let result = match (rounding, order) {
+        (_, Ordering::Equal) => nearest,
+        (Rounding::Nearest, _) if nearest.is_finite() && difference != 0.0 && residual != 0.0 => {
+            let neighbour = if difference > 0.0 { step_up(nearest) } else { step_down(nearest) };
+
+            // a tie in f64 that the residual breaks away from the nearest even single
+            match difference.abs() == (neighbour as f64 - nearest as f64).abs() / 2.0 && residual.signum() == difference.signum() {
+                true => neighbour,
+                false => nearest,
+            }
+        },
+        (Rounding::Nearest, _) => nearest,
+        (Rounding::Zero, Ordering::Greater) if nearest < 0.0 => step_up(nearest),
+        (Rounding::Zero, Ordering::Less) if nearest > 0.0 => step_down(nearest),
+        (Rounding::PlusInfinity, Ordering::Greater) => step_up(nearest),
+        (Rounding::MinusInfinity, Ordering::Less) => step_down(nearest),
+        _ => nearest,
+    };
+
+    if order != Ordering::Equal {
+        fpscr.raise(FpException::Inexact);
+
+        if result.is_infinite() || value.abs() > f32::MAX as f64 {
+            fpscr.raise(FpException::Overflow);
+        }
+
+        if value.abs() < f32::MIN_POSITIVE as f64 {
+            fpscr.raise(FpException::Underflow);
+        }
+    }
+
+    result.to_bits()
+}
+
+// the exact sum of two f64 values is sum + residual
+fn two_sum(x: f64, y: f64) -> (f64, f64) {
+    let sum = x + y;
+    let virtual_y = sum - x;
+
+    (sum, (x - (sum - virtual_y)) + (y - virtual_y))
+}
+
+// x + y is zero only by cancellation or when both are zero, page 92 @ ARMv7M Reference Manual
+fn zero_sum(fpscr: &Fpscr, x: f64, y: f64) -> u32 {
+    match x == 0.0 && y == 0.0 && x.is_sign_negative() == y.is_sign_negative() {
+        true => signed_zero(x.is_sign_negative()),
+        false => signed_zero(fpscr.rounding() == Rounding::MinusInfinity),
+    }
+}
+
+pub fn add(fpscr: &mut Fpscr, a: u32, b: u32) -> u32 {
+    if let Some(nan) = process_nans(fpscr, &[a, b]) {
+        return nan;
+    }
+
+    let (x, y) = (unpack(fpscr, a), unpack(fpscr, b));
+
+    if x.is_infinite() && y.is_infinite() && x.is_sign_negative() != y.is_sign_negative() {
+        return invalid(fpscr);
+    }
+
+    match two_sum(x, y) {
+        (sum, residual) if sum == 0.0 && residual == 0.0 => zero_sum(fpscr, x, y),
+        (sum, residual) if sum.is_finite() => round(fpscr, sum, residual, fpscr.rounding()),
+        (sum, _) => round(fpscr, sum, 0.0, fpscr.rounding()),
+    }
+}
+
+pub fn sub(fpscr: &mut Fpscr, a: u32, b: u32) -> u32 {
+    match process_nans(fpscr, &[a, b]) {
+        Some(nan) => nan,
+        None => add(fpscr, a, negate(b)),
+    }
+}
+
+pub fn mul(fpscr: &mut Fpscr, a: u32, b: u32) -> u32 {
+    if let Some(nan) = process_nans(fpscr, &[a, b]) {
+        return nan;
+    }
+
+    let (x, y) = (unpack(fpscr, a), unpack(fpscr, b));
+
+    if (x.is_infinite() && y == 0.0) || (x == 0.0 && y.is_infinite()) {
+        return invalid(fpscr);
+    }
+
+    // the product of two singles is exact in f64
+    round(fpscr, x * y, 0.0, fpscr.rounding())
+}
+
+pub fn div(fpscr: &mut Fpscr, a: u32, b: u32) -> u32 {
+    if let Some(nan) = process_nans(fpscr, &[a, b]) {
+        return nan;
+    }
+
+    let (x, y) = (unpack(fpscr, a), unpack(fpscr, b));
+
+    if (x.is_infinite() && y.is_infinite()) || (x == 0.0 && y == 0.0) {
+        return invalid(fpscr);
+    }
+
+    if y == 0.0 && x.is_finite() {
+        fpscr.raise(FpException::DivideByZero);
+    }
+
+    let quotient = x / y;
+
+    match quotient.is_finite() && y.is_finite() {
+        true => round(fpscr, quotient, (-quotient).mul_add(y, x) / y, fpscr.rounding()),
+        false => round(fpscr, quotient, 0.0, fpscr.rounding()),
+    }
+}
+
+pub fn sqrt(fpscr: &mut Fpscr, a: u32) -> u32 {
+    if let Some(nan) = process_nans(fpscr, &[a]) {
+        return nan;
+    }
+
+    let x = unpack(fpscr, a);
+
+    if x < 0.0 {
+        return invalid(fpscr);
+    }
+
+    let root = x.sqrt();
+
+    match root.is_finite() {
+        true => round(fpscr, root, (-root).mul_add(root, x), fpscr.rounding()),
+        false => round(fpscr, root, 0.0, fpscr.rounding()),
+    }
+}
+
+// addend + a * b with a single rounding, page 93 @ ARMv7M Reference Manual
+pub fn mul_add(fpscr: &mut Fpscr, addend: u32, a: u32, b: u32) -> u32 {
+    let (x, y) = (f32::from_bits(a), f32::from_bits(b));
+    let infinite_zero = (x.is_infinite() && y == 0.0) || (x == 0.0 && y.is_infinite());
+
+    if is_nan(addend) && !is_signalling(addend) && infinite_zero {
+        return invalid(fpscr);
+    }
+
+    if let Some(nan) = process_nans(fpscr, &[addend, a, b]) {
+        return nan;
+    }
+
+    let (z, x, y) = (unpack(fpscr, addend), unpack(fpscr, a), unpack(fpscr, b));
+    let product = x * y;
+
+    if infinite_zero || (z.is_infinite() && product.is_infinite() && z.is_sign_negative() != product.is_sign_negative()) {
+        return invalid(fpscr);
+    }
+
+    match two_sum(product, z) {
+        (sum, residual) if sum == 0.0 && residual == 0.0 => zero_sum(fpscr, product, z),
+        (sum, residual) if sum.is_finite() => round(fpscr, sum, residual, fpscr.rounding()),
+        (sum, _) => round(fpscr, sum, 0.0, fpscr.rounding()),
+    }
+}
+
+// returns the nzcv flags, page 87 @ ARMv7M Reference Manual
+pub fn compare(fpscr: &mut Fpscr, a: u32, b: u32, signal: bool) -> u32 {
+    if is_nan(a) || is_nan(b) {
+        if signal || is_signalling(a) || is_signalling(b) {
+            fpscr.raise(FpException::InvalidOperation);
+        }
+
+        return 0b0011;
+    }
+
+    match unpack(fpscr, a).partial_cmp(&unpack(fpscr, b)) {
+        Some(Ordering::Equal) => 0b0110,
+        Some(Ordering::Less) => 0b1000,
+        _ => 0b0010,
+    }
+}
+
+// page 95 @ ARMv7M Reference Manual
+pub fn to_fixed(fpscr: &mut Fpscr, a: u32, signed: bool, bits: u32, fbits: u32, rounding: Rounding) -> u32 {
+    if is_nan(a) {
+        fpscr.raise(FpException::InvalidOperation);
+
+        return 0;
+    }
+
+    let value = unpack(fpscr, a) * (1u64 << fbits) as f64;
+
+    let rounded = match rounding {
+        Rounding::Nearest => value.round_ties_even(),
+        Rounding::PlusInfinity => value.ceil(),
+        Rounding::MinusInfinity => value.floor(),
+        Rounding::Zero => value.trunc(),
+    };
+
+    let (min, max) = match signed {
+        true => (-((1u64 << (bits - 1)) as f64), ((1u64 << (bits - 1)) - 1) as f64),
+        false => (0.0, ((1u64 << bits) - 1) as f64),
+    };
+
+    if rounded < min || rounded > max {
+        fpscr.raise(FpException::InvalidOperation);
+
+        return rounded.clamp(min, max) as i64 as u32;
+    }
+
+    if rounded != value {
+        fpscr.raise(FpException::Inexact);
+    }
+
+    rounded as i64 as u32
+}
+
+pub fn from_fixed(fpscr: &mut Fpscr, value: u32, signed: bool, bits: u32, fbits: u32, rounding: Rounding) -> u32 {
+    let integer = match (signed, bits) {
+        (true, 16) => value as i16 as f64,
+        (true, _) => value as i32 as f64,
+        (false, 16) => (value & 0xffff) as f64,
+        (false, _) => value as f64,
+    };
+
+    round(fpscr, integer / (1u64 << fbits) as f64, 0.0, rounding)
+}
+
+// half precision operands are never flushed to zero, with FPSCR.AHP set there are no infinities or nans
+pub fn half_to_single(fpscr: &mut Fpscr, half: u32) -> u32 {
+    let (sign, exponent, fraction) = ((half >> 15) & 1, ((half >> 10) & 0x1f) as i32, half & 0x3ff);
+
+    if exponent == 0x1f && !fpscr.alternative_half() {
+        return match fraction {
+            0 => (sign << 31) | 0x7f800000,
+            _ => {
+                if fraction & (1 << 9) == 0 {
+                    fpscr.raise(FpException::InvalidOperation);
+                }
+
+                if fpscr.default_nan() { DEFAULT_NAN } else { (sign << 31) | 0x7fc00000 | ((fraction & 0x1ff) << 13) }
+            },
+        };
+    }
+
+    // every half precision value is exact in single precision
+    let magnitude = match exponent {
+        0 => fraction as f32 * 2.0f32.powi(-24),
+        _ => (0x400 | fraction) as f32 * 2.0f32.powi(exponent - 25),
+    };
+
+    (sign << 31) | magnitude.to_bits()
+}
+
+// rounds like FPRound with a 5 bit exponent, half precision results are never flushed to zero either
+pub fn single_to_half(fpscr: &mut Fpscr, a: u32) -> u32 {
+    let (sign, alternative) = ((a >> 31) << 15, fpscr.alternative_half());
+
+    if is_nan(a) {
+        if alternative || is_signalling(a) {
+            fpscr.raise(FpException::InvalidOperation);
+        }
+
+        return match (alternative, fpscr.default_nan()) {
+            (true, _) => sign,
+            (false, true) => 0x7e00,
+            (false, false) => sign | 0x7e00 | ((a >> 13) & 0x1ff),
+        };
+    }
+
+    let value = unpack(fpscr, a).abs();
+
+    if value.is_infinite() {
+        if alternative {
+            fpscr.raise(FpException::InvalidOperation);
+        }
+
+        return sign | if alternative { 0x7fff } else { 0x7c00 };
+    }
+
+    if value == 0.0 {
+        return sign;
+    }
+
+    // the f64 exponent of a value that came from a single is exact
+    let exponent = ((value.to_bits() >> 52) & 0x7ff) as i32 - 1023;
+
+    // normal mantissas keep their leading one, so they sit at or above 0x400
+    let (mut biased, mantissa) = match exponent < -14 {
+        true => (0, value / 2.0f64.powi(-14)),
+        false => (exponent + 15, value / 2.0f64.powi(exponent)),
+    };
+
+    let mut integer = (mantissa * 1024.0).floor() as u32;
+    let error = mantissa * 1024.0 - integer as f64;
+
+    if biased == 0 && error != 0.0 {
+        fpscr.raise(FpException::Underflow);
+    }
+
+    let negative = sign != 0;
+
+    let round_up = match fpscr.rounding() {
+        Rounding::Nearest => error > 0.5 || (error == 0.5 && integer & 1 != 0),
+        Rounding::PlusInfinity => error != 0.0 && !negative,
+        Rounding::MinusInfinity => error != 0.0 && negative,
+        Rounding::Zero => false,
+    };
+
+    if round_up {
+        integer += 1;
+
+        // a denormal that rounds up into the smallest normal
+        if integer == 0x400 {
+            biased = 1;
+        }
+
+        if integer == 0x800 {
+            integer >>= 1;
+            biased += 1;
+        }
+    }
+
+    if alternative && biased >= 32 {
+        fpscr.raise(FpException::InvalidOperation);
+
+        return sign | 0x7fff;
+    }
+
+    if !alternative && biased >= 31 {
+        fpscr.raise(FpException::Overflow);
+        fpscr.raise(FpException::Inexact);
+
+        let infinite = match fpscr.rounding() {
+            Rounding::Nearest => true,
+            Rounding::PlusInfinity => !negative,
+            Rounding::MinusInfinity => negative,
+            Rounding::Zero => false,
+        };
+
+        return sign | if infinite { 0x7c00 } else { 0x7bff };
+    }
+
+    if error != 0.0 {
+        fpscr.raise(FpException::Inexact);
+    }
+
+    sign | ((biased as u32) << 10) | (integer & 0x3ff)
+}
+
+impl Processor {
+    // page 613 @ ARMv7M Reference Manual
+    fn fpu_enabled(&self) -> bool {
+        self.registers.cpacr.enabled(self.privileged())
+    }
+
+    // stores s0-s15 followed by fpscr, the layout of the extended frame past the basic one
+    pub fn preserve_fp(&mut self, addr: u32) {
+        for index in 0..16 {
+            self.write::<u32>(addr.wrapping_add(index as u32 * 4) as usize, self.registers.fp[index]);
+        }
+
+        self.write::<u32>(addr.wrapping_add(0x40) as usize, self.registers.fpscr.value);
+    }
+
+    pub fn restore_fp(&mut self, addr: u32) {
+        for index in 0..16 {
+            self.registers.fp[index] = self.read::<u32>(addr.wrapping_add(index as u32 * 4) as usize);
+        }
+
+        self.registers.fpscr.value = self.read::<u32>(addr.wrapping_add(0x40) as usize);
+    }
+
+    fn load_fp(&mut self, addr: u32, register: FpRegister) {
+        match register.double {
+            true => {
+                let (low, high) = (self.read::<u32>(addr as usize), self.read::<u32>(addr as usize + 4));
+
+                if self.bus_error.is_some() {
+                    return;
+                }
+
+                self.registers.fp[register.index as usize * 2] = low;
+                self.registers.fp[register.index as usize * 2 + 1] = high;
+            },
+            false => {
+                let value = self.read::<u32>(addr as usize);
+
+                if self.bus_error.is_none() {
+                    self.registers.fp[register.index as usize] = value;
+                }
+            },
+        }
+    }
+
+    fn store_fp(&mut self, addr: u32, register: FpRegister) {
+        match register.double {
+            true => {
+                self.write::<u32>(addr as usize, self.registers.fp[register.index as usize * 2]);
+                self.write::<u32>(addr as usize + 4, self.registers.fp[register.index as usize * 2 + 1]);
+            },
+            false => self.write::<u32>(addr as usize, self.registers.fp[register.index as usize]),
+        }
+    }
+
+    fn vfp(&mut self, op: VfpOp, sd: u8, sn: u8, sm: u8) -> u32 {
+        let (d, n, m) = (self.registers.fp[sd as usize], self.registers.fp[sn as usize], self.registers.fp[sm as usize]);
+        let fpscr = &mut self.registers.fpscr;
+
+        match op {
+            VfpOp::Add => add(fpscr, n, m),
+            VfpOp::Sub => sub(fpscr, n, m),
+            VfpOp::Mul => mul(fpscr, n, m),
+            VfpOp::Nmul => negate(mul(fpscr, n, m)),
+            VfpOp::Div => div(fpscr, n, m),
+            VfpOp::Mla => {
+                let product = mul(fpscr, n, m);
+
+                add(fpscr, d, product)
+            },
+            VfpOp::Mls => {
+                let product = mul(fpscr, n, m);
+
+                add(fpscr, d, negate(product))
+            },
+            VfpOp::Nmla => {
+                let product = mul(fpscr, n, m);
+
+                add(fpscr, negate(d), negate(product))
+            },
+            VfpOp::Nmls => {
+                let product = mul(fpscr, n, m);
+
+                add(fpscr, negate(d), product)
+            },
+            VfpOp::Fma => mul_add(fpscr, d, n, m),
+            VfpOp::Fms => mul_add(fpscr, d, negate(n), m),
+            VfpOp::Fnma => mul_add(fpscr, negate(d), negate(n), m),
+            VfpOp::Fnms => mul_add(fpscr, negate(d), n, m),
+        }
+    }
+
+    pub fn dispatch_fp(&mut self, kind: &InstructionKind) {
+        if !self.fpu_enabled() {
+            self.fault(Fault::NoCoprocessor);
+
+            return;
+        }
+
+        // page 561 @ ARMv7M Reference Manual
+        if self.registers.fpccr.lspact() {
+            self.preserve_fp(self.registers.fpcar);
+
+            self.registers.fpccr.set_lspact(false);
+
+            if self.bus_error.take().is_some() {
+                self.fault(Fault::LazyStacking);
+
+                return;
+            }
+        }
+
+        if self.registers.fpccr.aspen() && !self.registers.control.fpca {
+            self.registers.fpscr.value = (self.registers.fpscr.value & !0x07c00000) | self.registers.fpdscr;
+
+            self.registers.control.fpca = true;
+        }
+
+        match *kind {
+            InstructionKind::Vldr { vd, address } => {
+                let (addr, _) = self.address(address);
+
+                if !self.aligned(addr, 4, true) {
+                    return;
+                }
+
+                self.load_fp(addr, vd);
+            },
+            InstructionKind::Vstr { vd, address } => {
+                let (addr, _) = self.address(address);
+
+                if !self.aligned(addr, 4, true) {
+                    return;
+                }
+
+                self.store_fp(addr, vd);
+            },
+            InstructionKind::Vldm { rn, vd, count, add, wback } | InstructionKind::Vstm { rn, vd, count, add, wback } => {
+                let size = if vd.double { 8 } else { 4 } * count as u32;
+                let base = self.registers.get(rn, self.mode);
+                let start = if add { base } else { base.wrapping_sub(size) };
+
+                if !self.aligned(start, 4, true) {
+                    return;
+                }
+
+                let fp = self.registers.fp;
+
+                for (offset, index) in (vd.index..vd.index + count).enumerate() {
+                    let (addr, register) = (start.wrapping_add(offset as u32 * (size / count as u32)), FpRegister { index, ..vd });
+
+                    match kind {
+                        InstructionKind::Vldm { .. } => self.load_fp(addr, register),
+                        _ => self.store_fp(addr, register),
+                    }
+                }
+
+                // registers loaded before the faulting word are rolled back
+                if self.bus_error.is_some() {
+                    self.registers.fp = fp;
+
+                    return;
+                }
+
+                if wback {
+                    self.registers.set(rn, |_| if add { base.wrapping_add(size) } else { start }, self.mode);
+                }
+            },
+            InstructionKind::VmovCore { sn, rt, to_core: true } => {
+                let value = self.registers.fp[sn as usize];
+
+                self.registers.set(rt, |_| value, self.mode);
+            },
+            InstructionKind::VmovCore { sn, rt, to_core: false } => {
+                self.registers.fp[sn as usize] = self.registers.get(rt, self.mode);
+            },
+            InstructionKind::VmovCorePair { vm, rt, rt2, to_core } => {
+                let low = if vm.double { vm.index as usize * 2 } else { vm.index as usize };
+
+                if to_core {
+                    let (first, second) = (self.registers.fp[low], self.registers.fp[low + 1]);
+
+                    self.registers.set(rt, |_| first, self.mode);
+                    self.registers.set(rt2, |_| second, self.mode);
+                } else {
+                    self.registers.fp[low] = self.registers.get(rt, self.mode);
+                    self.registers.fp[low + 1] = self.registers.get(rt2, self.mode);
+                }
+            },
+            InstructionKind::VmovImm { sd, imm32 } => self.registers.fp[sd as usize] = imm32,
+            InstructionKind::Vfp { op, sd, sn, sm } => self.registers.fp[sd as usize] = self.vfp(op, sd, sn, sm),
+            InstructionKind::VfpUnary { op, sd, sm } => {
+                let m = self.registers.fp[sm as usize];
+
+                self.registers.fp[sd as usize] = match op {
+                    VfpUnaryOp::Mov => m,
+                    VfpUnaryOp::Abs => m & !(1 << 31),
+                    VfpUnaryOp::Neg => negate(m),
+                    VfpUnaryOp::Sqrt => sqrt(&mut self.registers.fpscr, m),
+                };
+            },
+            InstructionKind::Vcmp { sd, sm, signal } => {
+                let (d, m) = (self.registers.fp[sd as usize], sm.map(|sm| self.registers.fp[sm as usize]).unwrap_or(0));
+
+                let nzcv = compare(&mut self.registers.fpscr, d, m, signal);
+
+                self.registers.fpscr.set_nzcv(nzcv);
+            },
+            InstructionKind::VcvtInt { sd, sm, signed, to_int, round_zero } => {
+                let (m, fpscr) = (self.registers.fp[sm as usize], &mut self.registers.fpscr);
+                let rounding = if round_zero { Rounding::Zero } else { fpscr.rounding() };
+
+                self.registers.fp[sd as usize] = match to_int {
+                    true => to_fixed(fpscr, m, signed, 32, 0, rounding),
+                    false => from_fixed(fpscr, m, signed, 32, 0, fpscr.rounding()),
+                };
+            },
+            InstructionKind::VcvtFixed { sd, signed, half, fbits, to_fixed: true } => {
+                let bits = if half { 16 } else { 32 };
+                let result = to_fixed(&mut self.registers.fpscr, self.registers.fp[sd as usize], signed, bits, fbits as u32, Rounding::Zero);
+
+                self.registers.fp[sd as usize] = match (half, signed) {
+                    (true, true) => result as i16 as u32,
+                    (true, false) => result & 0xffff,
+                    (false, _) => result,
+                };
+            },
+            InstructionKind::VcvtFixed { sd, signed, half, fbits, to_fixed: false } => {
+                let bits = if half { 16 } else { 32 };
+
+                self.registers.fp[sd as usize] = from_fixed(&mut self.registers.fpscr, self.registers.fp[sd as usize], signed, bits, fbits as u32, Rounding::Nearest);
+            },
+            InstructionKind::VcvtHalf { sd, sm, top, to_half: false } => {
+                let half = self.registers.fp[sm as usize] >> if top { 16 } else { 0 };
+
+                self.registers.fp[sd as usize] = half_to_single(&mut self.registers.fpscr, half & 0xffff);
+            },
+            InstructionKind::VcvtHalf { sd, sm, top, to_half: true } => {
+                let (shift, half) = (if top { 16 } else { 0 }, single_to_half(&mut self.registers.fpscr, self.registers.fp[sm as usize]));
+
+                self.registers.fp[sd as usize] = (self.registers.fp[sd as usize] & !(0xffff << shift)) | (half << shift);
+            },
+            InstructionKind::Vmrs { rt: 15 } => {
+                for flag in [Flag::N, Flag::Z, Flag::C, Flag::V] {
+                    self.registers.psr.set_flag(flag, self.registers.fpscr.flag(flag));
+                }
+            },
+            InstructionKind::Vmrs { rt } => {
+                let value = self.registers.fpscr.value;
+
+                self.registers.set(rt, |_| value, self.mode);
+            },
+            InstructionKind::Vmsr { rt } => {
+                self.registers.fpscr.value = self.registers.get(rt, self.mode) & 0xf7c0009f;
+            },
+            _ => unreachable!("not a floating point instruction"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fpscr(rounding: u32) -> Fpscr {
+        Fpscr { value: rounding << 22 }
+    }
+
+    fn bits(x: f32) -> u32 {
+        x.to_bits()
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut status = fpscr(0);
+
+        assert_eq!(add(&mut status, bits(1.5), bits(2.25)), bits(3.75));
+        assert_eq!(sub(&mut status, bits(1.0), bits(1.0)), bits(0.0));
+        assert_eq!(mul(&mut status, bits(-2.0), bits(3.5)), bits(-7.0));
+        assert_eq!(div(&mut status, bits(1.0), bits(4.0)), bits(0.25));
+        assert_eq!(sqrt(&mut status, bits(2.25)), bits(1.5));
+        assert_eq!(mul_add(&mut status, bits(1.0), bits(2.0), bits(3.0)), bits(7.0));
+        assert_eq!(status.value, 0);
+
+        assert_eq!(div(&mut status, bits(1.0), bits(3.0)), bits(1.0 / 3.0));
+        assert_eq!(sqrt(&mut status, bits(2.0)), bits(2.0f32.sqrt()));
+        assert_eq!(status.value, 1 << FpException::Inexact as u32);
+    }
+
+    #[test]
+    fn rounding_modes() {
+        let third = |rounding| div(&mut fpscr(rounding), bits(1.0), bits(3.0));
+
+        assert_eq!(third(0b00), 0x3eaaaaab);
+        assert_eq!(third(0b01), 0x3eaaaaab);
+        assert_eq!(third(0b10), 0x3eaaaaaa);
+        assert_eq!(third(0b11), 0x3eaaaaaa);
+
+        assert_eq!(add(&mut fpscr(0b01), bits(1.0), bits(1e-30)), bits(1.0) + 1);
+        assert_eq!(add(&mut fpscr(0b10), bits(1.0), bits(-1.0)), bits(-0.0));
+        assert_eq!(mul(&mut fpscr(0b11), bits(f32::MAX), bits(2.0)), bits(f32::MAX));
+        assert_eq!(to_fixed(&mut fpscr(0), bits(2.5), true, 32, 0, Rounding::Nearest), 2);
+        assert_eq!(to_fixed(&mut fpscr(0), bits(-2.5), true, 32, 0, Rounding::MinusInfinity), -3i32 as u32);
+    }
+
+    #[test]
+    fn exceptions() {
+        let mut status = fpscr(0);
+
+        assert_eq!(div(&mut status, bits(1.0), bits(0.0)), bits(f32::INFINITY));
+        assert_eq!(status.value, 1 << FpException::DivideByZero as u32);
+
+        let mut status = fpscr(0);
+
+        assert_eq!(sqrt(&mut status, bits(-1.0)), DEFAULT_NAN);
+        assert_eq!(add(&mut status, bits(f32::INFINITY), bits(f32::NEG_INFINITY)), DEFAULT_NAN);
+        assert_eq!(status.value, 1 << FpException::InvalidOperation as u32);
+
+        let mut status = fpscr(0);
+
+        assert_eq!(mul(&mut status, bits(f32::MAX), bits(2.0)), bits(f32::INFINITY));
+        assert_eq!(status.value, (1 << FpException::Overflow as u32) | (1 << FpException::Inexact as u32));
+
+        let mut status = fpscr(0);
+
+        assert_eq!(mul(&mut status, bits(f32::MIN_POSITIVE), bits(0.3)), bits(f32::MIN_POSITIVE * 0.3));
+        assert_eq!(status.value, (1 << FpException::Underflow as u32) | (1 << FpException::Inexact as u32));
+
+        let mut status = fpscr(0);
+
+        assert_eq!(to_fixed(&mut status, bits(5e9), false, 32, 0, Rounding::Zero), 0xffffffff);
+        assert_eq!(to_fixed(&mut status, 0x7f800001, true, 32, 0, Rounding::Zero), 0);
+        assert_eq!(status.value, 1 << FpException::InvalidOperation as u32);
+    }
+
+    #[test]
+    fn nans_and_flush_to_zero() {
+        let mut status = fpscr(0);
+
+        assert_eq!(add(&mut status, 0x7f800001, bits(1.0)), 0x7fc00001);
+        assert_eq!(status.value, 1 << FpException::InvalidOperation as u32);
+        assert_eq!(mul(&mut fpscr(0), bits(1.0), 0xffc00002), 0xffc00002);
+        assert_eq!(add(&mut Fpscr { value: 1 << 25 }, 0xffc00002, bits(1.0)), DEFAULT_NAN);
+        assert_eq!(compare(&mut fpscr(0), 0x7fc00000, bits(1.0), false), 0b0011);
+        assert_eq!(compare(&mut fpscr(0), bits(1.0), bits(2.0), false), 0b1000);
+
+        let mut status = Fpscr { value: 1 << 24 };
+
+        assert_eq!(add(&mut status, 1, bits(0.0)), bits(0.0));
+        assert_eq!(status.value, (1 << 24) | (1 << FpException::InputDenormal as u32));
+    }
+
+    #[test]
+    fn half_precision() {
+        let mut status = fpscr(0);
+
+        assert_eq!(half_to_single(&mut status, 0x3c00), bits(1.0));
+        assert_eq!(half_to_single(&mut status, 0xc500), bits(-5.0));
+        assert_eq!(half_to_single(&mut status, 0x0001), bits(2.0f32.powi(-24)));
+        assert_eq!(half_to_single(&mut status, 0xfc00), bits(f32::NEG_INFINITY));
+        assert_eq!(half_to_single(&mut status, 0x7e01), 0x7fc02000);
+        assert_eq!(single_to_half(&mut status, bits(1.0)), 0x3c00);
+        assert_eq!(single_to_half(&mut status, bits(-65504.0)), 0xfbff);
+        assert_eq!(single_to_half(&mut status, bits(2.0f32.powi(-24))), 0x0001);
+        assert_eq!(status.value, 0);
+
+        assert_eq!(single_to_half(&mut status, bits(1.0 + 2.0f32.powi(-11))), 0x3c00);
+        assert_eq!(single_to_half(&mut fpscr(0b01), bits(1.0 + 2.0f32.powi(-11))), 0x3c01);
+        assert_eq!(status.value, 1 << FpException::Inexact as u32);
+
+        let mut status = fpscr(0);
+
+        assert_eq!(single_to_half(&mut status, bits(65520.0)), 0x7c00);
+        assert_eq!(single_to_half(&mut fpscr(0b11), bits(65520.0)), 0x7bff);
+        assert_eq!(status.value, (1 << FpException::Overflow as u32) | (1 << FpException::Inexact as u32));
+
+        // the alternative format has no infinities or nans and reaches up to 131008
+        let mut status = Fpscr { value: 1 << 26 };
+
+        assert_eq!(half_to_single(&mut status, 0x7fff), bits(131008.0));
+        assert_eq!(single_to_half(&mut status, bits(70000.0)), 0x7c46);
+        assert_eq!(status.value, (1 << 26) | (1 << FpException::Inexact as u32));
+        assert_eq!(single_to_half(&mut status, bits(f32::INFINITY)), 0x7fff);
+        assert_eq!(status.value & 1, 1);
+    }
+}
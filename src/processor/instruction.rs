@@ -1,76 +1,1167 @@
 
 
-#[derive(Debug, Clone, Copy)]
-pub enum Source {
-    Imm8(u8),
-    Imm16(u16),
-    Imm32(u32),
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    Eq,
+    Ne,
+    Cs,
+    Cc,
+    Mi,
+    Pl,
+    Vs,
+    Vc,
+    Hi,
+    Ls,
+    Ge,
+    Lt,
+    Gt,
+    Le,
+    Al,
 }
 
-impl Into<u32> for Source {
-    fn into(self) -> u32 {
+impl From<u8> for Condition {
+    fn from(value: u8) -> Condition {
+        match value & 0xf {
+            0b0000 => Condition::Eq,
+            0b0001 => Condition::Ne,
+            0b0010 => Condition::Cs,
+            0b0011 => Condition::Cc,
+            0b0100 => Condition::Mi,
+            0b0101 => Condition::Pl,
+            0b0110 => Condition::Vs,
+            0b0111 => Condition::Vc,
+            0b1000 => Condition::Hi,
+            0b1001 => Condition::Ls,
+            0b1010 => Condition::Ge,
+            0b1011 => Condition::Lt,
+            0b1100 => Condition::Gt,
+            0b1101 => Condition::Le,
+            _ => Condition::Al,
+        }
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Condition::Eq => f.write_str("eq"),
+            Condition::Ne => f.write_str("ne"),
+            Condition::Cs => f.write_str("cs"),
+            Condition::Cc => f.write_str("cc"),
+            Condition::Mi => f.write_str("mi"),
+            Condition::Pl => f.write_str("pl"),
+            Condition::Vs => f.write_str("vs"),
+            Condition::Vc => f.write_str("vc"),
+            Condition::Hi => f.write_str("hi"),
+            Condition::Ls => f.write_str("ls"),
+            Condition::Ge => f.write_str("ge"),
+            Condition::Lt => f.write_str("lt"),
+            Condition::Gt => f.write_str("gt"),
+            Condition::Le => f.write_str("le"),
+            Condition::Al => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShiftKind {
+    Lsl,
+    Lsr,
+    Asr,
+    Ror,
+    Rrx,
+}
+
+impl std::fmt::Display for ShiftKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ShiftKind::Lsl => f.write_str("lsl"),
+            ShiftKind::Lsr => f.write_str("lsr"),
+            ShiftKind::Asr => f.write_str("asr"),
+            ShiftKind::Ror => f.write_str("ror"),
+            ShiftKind::Rrx => f.write_str("rrx"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shift {
+    pub kind: ShiftKind,
+    pub amount: u8,
+}
+
+impl Shift {
+    pub fn none() -> Shift {
+        Shift {
+            kind: ShiftKind::Lsl,
+            amount: 0,
+        }
+    }
+
+    // page 181 @ ARMv7M Reference Manual
+    pub fn decode(kind: u8, imm5: u8) -> Shift {
+        match (kind, imm5) {
+            (0b00, _) => Shift { kind: ShiftKind::Lsl, amount: imm5 },
+            (0b01, 0) => Shift { kind: ShiftKind::Lsr, amount: 32 },
+            (0b01, _) => Shift { kind: ShiftKind::Lsr, amount: imm5 },
+            (0b10, 0) => Shift { kind: ShiftKind::Asr, amount: 32 },
+            (0b10, _) => Shift { kind: ShiftKind::Asr, amount: imm5 },
+            (_, 0) => Shift { kind: ShiftKind::Rrx, amount: 1 },
+            _ => Shift { kind: ShiftKind::Ror, amount: imm5 },
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.kind == ShiftKind::Lsl && self.amount == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    Imm {
+        imm32: u32,
+        carry: Option<bool>,
+    },
+    Reg {
+        rm: u8,
+        shift: Shift,
+    },
+}
+
+impl Operand {
+    pub fn imm(imm32: u32) -> Operand {
+        Operand::Imm {
+            imm32,
+            carry: None,
+        }
+    }
+
+    pub fn reg(rm: u8) -> Operand {
+        Operand::Reg {
+            rm,
+            shift: Shift::none(),
+        }
+    }
+}
+
+impl std::fmt::Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Operand::Imm { imm32, .. } => f.write_fmt(format_args!("#{}", imm32)),
+            Operand::Reg { rm, shift } if shift.is_none() => f.write_fmt(format_args!("{}", Register(*rm))),
+            Operand::Reg { rm, shift } if shift.kind == ShiftKind::Rrx => f.write_fmt(format_args!("{}, rrx", Register(*rm))),
+            Operand::Reg { rm, shift } => f.write_fmt(format_args!("{}, {} #{}", Register(*rm), shift.kind, shift.amount)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Width {
+    Byte,
+    Halfword,
+    Word,
+}
+
+impl Width {
+    pub fn size(&self) -> u32 {
+        match self {
+            Width::Byte => 1,
+            Width::Halfword => 2,
+            Width::Word => 4,
+        }
+    }
+}
+
+impl std::fmt::Display for Width {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Width::Byte => f.write_str("b"),
+            Width::Halfword => f.write_str("h"),
+            Width::Word => Ok(()),
+        }
+    }
+}
+
+// page 152 and 153 @ ARMv7M Reference Manual
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParallelOp {
+    Add16,
+    Asx,
+    Sax,
+    Sub16,
+    Add8,
+    Sub8,
+}
+
+impl std::fmt::Display for ParallelOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ParallelOp::Add16 => f.write_str("add16"),
+            ParallelOp::Asx => f.write_str("asx"),
+            ParallelOp::Sax => f.write_str("sax"),
+            ParallelOp::Sub16 => f.write_str("sub16"),
+            ParallelOp::Add8 => f.write_str("add8"),
+            ParallelOp::Sub8 => f.write_str("sub8"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParallelPrefix {
+    Signed,
+    Saturating,
+    Halving,
+    Unsigned,
+    UnsignedSaturating,
+    UnsignedHalving,
+}
+
+impl ParallelPrefix {
+    pub fn signed(&self) -> bool {
+        matches!(self, ParallelPrefix::Signed | ParallelPrefix::Saturating | ParallelPrefix::Halving)
+    }
+}
+
+impl std::fmt::Display for ParallelPrefix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ParallelPrefix::Signed => f.write_str("s"),
+            ParallelPrefix::Saturating => f.write_str("q"),
+            ParallelPrefix::Halving => f.write_str("sh"),
+            ParallelPrefix::Unsigned => f.write_str("u"),
+            ParallelPrefix::UnsignedSaturating => f.write_str("uq"),
+            ParallelPrefix::UnsignedHalving => f.write_str("uh"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Offset {
+    Imm(u32),
+    Reg {
+        rm: u8,
+        shift: u8,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Address {
+    pub rn: u8,
+    pub offset: Offset,
+    pub index: bool,
+    pub add: bool,
+    pub wback: bool,
+}
+
+impl Address {
+    pub fn new(rn: u8, offset: Offset) -> Address {
+        Address {
+            rn,
+            offset,
+            index: true,
+            add: true,
+            wback: false,
+        }
+    }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let sign = if self.add { "" } else { "-" };
+
+        let offset = match self.offset {
+            Offset::Imm(0) if self.index && !self.wback => String::new(),
+            Offset::Imm(imm32) => format!(", #{}{}", sign, imm32),
+            Offset::Reg { rm, shift: 0 } => format!(", {}{}", sign, Register(rm)),
+            Offset::Reg { rm, shift } => format!(", {}{}, lsl #{}", sign, Register(rm), shift),
+        };
+
+        match (self.index, self.wback) {
+            (true, false) => f.write_fmt(format_args!("[{}{}]", Register(self.rn), offset)),
+            (true, true) => f.write_fmt(format_args!("[{}{}]!", Register(self.rn), offset)),
+            _ => f.write_fmt(format_args!("[{}]{}", Register(self.rn), offset)),
+        }
+    }
+}
+
+// only the full system option is architected, the rest are reserved and shown as raw values
+pub struct Barrier(pub u8);
+
+impl std::fmt::Display for Barrier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self.0 {
+            0b1111 => f.write_str("sy"),
+            option => f.write_fmt(format_args!("#{}", option)),
+        }
+    }
+}
+
+// page 519 @ ARMv7M Reference Manual
+pub struct SpecialRegister(pub u8);
+
+impl std::fmt::Display for SpecialRegister {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self.0 {
+            0 => f.write_str("APSR"),
+            1 => f.write_str("IAPSR"),
+            2 => f.write_str("EAPSR"),
+            3 => f.write_str("XPSR"),
+            5 => f.write_str("IPSR"),
+            6 => f.write_str("EPSR"),
+            7 => f.write_str("IEPSR"),
+            8 => f.write_str("MSP"),
+            9 => f.write_str("PSP"),
+            16 => f.write_str("PRIMASK"),
+            17 => f.write_str("BASEPRI"),
+            18 => f.write_str("BASEPRI_MAX"),
+            19 => f.write_str("FAULTMASK"),
+            20 => f.write_str("CONTROL"),
+            sysm => f.write_fmt(format_args!("{}", sysm)),
+        }
+    }
+}
+
+pub struct Register(pub u8);
+
+impl std::fmt::Display for Register {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self.0 {
+            13 => f.write_str("sp"),
+            14 => f.write_str("lr"),
+            15 => f.write_str("pc"),
+            register => f.write_fmt(format_args!("r{}", register)),
+        }
+    }
+}
+
+// single precision registers alias the low and high halves of the double precision registers, page 176 @ ARMv7M Reference Manual
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FpRegister {
+    pub index: u8,
+    pub double: bool,
+}
+
+impl FpRegister {
+    pub fn single(index: u8) -> FpRegister {
+        FpRegister {
+            index,
+            double: false,
+        }
+    }
+
+    pub fn double(index: u8) -> FpRegister {
+        FpRegister {
+            index,
+            double: true,
+        }
+    }
+}
+
+impl std::fmt::Display for FpRegister {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.write_fmt(format_args!("{}{}", if self.double { "d" } else { "s" }, self.index))
+    }
+}
+
+pub struct FpRegisterList(pub FpRegister, pub u8);
+
+impl std::fmt::Display for FpRegisterList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let last = FpRegister { index: self.0.index + self.1 - 1, ..self.0 };
+
+        match self.1 {
+            1 => f.write_fmt(format_args!("{{{}}}", self.0)),
+            _ => f.write_fmt(format_args!("{{{}-{}}}", self.0, last)),
+        }
+    }
+}
+
+// page 168 @ ARMv7M Reference Manual
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VfpOp {
+    Add,
+    Sub,
+    Mul,
+    Nmul,
+    Div,
+    Mla,
+    Mls,
+    Nmla,
+    Nmls,
+    Fma,
+    Fms,
+    Fnma,
+    Fnms,
+}
+
+impl std::fmt::Display for VfpOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            VfpOp::Add => f.write_str("vadd"),
+            VfpOp::Sub => f.write_str("vsub"),
+            VfpOp::Mul => f.write_str("vmul"),
+            VfpOp::Nmul => f.write_str("vnmul"),
+            VfpOp::Div => f.write_str("vdiv"),
+            VfpOp::Mla => f.write_str("vmla"),
+            VfpOp::Mls => f.write_str("vmls"),
+            VfpOp::Nmla => f.write_str("vnmla"),
+            VfpOp::Nmls => f.write_str("vnmls"),
+            VfpOp::Fma => f.write_str("vfma"),
+            VfpOp::Fms => f.write_str("vfms"),
+            VfpOp::Fnma => f.write_str("vfnma"),
+            VfpOp::Fnms => f.write_str("vfnms"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VfpUnaryOp {
+    Mov,
+    Abs,
+    Neg,
+    Sqrt,
+}
+
+impl std::fmt::Display for VfpUnaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         match self {
-            Source::Imm8(value) => value as u32,
-            Source::Imm16(value) => value as u32,
-            Source::Imm32(value) => value,
+            VfpUnaryOp::Mov => f.write_str("vmov"),
+            VfpUnaryOp::Abs => f.write_str("vabs"),
+            VfpUnaryOp::Neg => f.write_str("vneg"),
+            VfpUnaryOp::Sqrt => f.write_str("vsqrt"),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReverseOp {
+    Rev,
+    Rev16,
+    Revsh,
+    Rbit,
+}
+
+impl std::fmt::Display for ReverseOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ReverseOp::Rev => f.write_str("rev"),
+            ReverseOp::Rev16 => f.write_str("rev16"),
+            ReverseOp::Revsh => f.write_str("revsh"),
+            ReverseOp::Rbit => f.write_str("rbit"),
+        }
+    }
+}
+
+pub struct RegisterList(pub u16);
+
+impl std::fmt::Display for RegisterList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let registers = (0..16)
+            .filter(|register| self.0 & (1 << register) != 0)
+            .map(|register| Register(register).to_string())
+            .collect::<Vec<String>>();
+
+        f.write_fmt(format_args!("{{{}}}", registers.join(", ")))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum InstructionKind {
     Mov {
-        register: u8,
-        source: Source,
+        rd: u8,
+        operand: Operand,
+        setflags: bool,
+    },
+    Mvn {
+        rd: u8,
+        operand: Operand,
+        setflags: bool,
     },
     Add {
+        rd: u8,
+        rn: u8,
+        operand: Operand,
+        setflags: bool,
+    },
+    Adc {
+        rd: u8,
+        rn: u8,
+        operand: Operand,
+        setflags: bool,
+    },
+    Sub {
+        rd: u8,
+        rn: u8,
+        operand: Operand,
+        setflags: bool,
+    },
+    Sbc {
+        rd: u8,
+        rn: u8,
+        operand: Operand,
+        setflags: bool,
+    },
+    Rsb {
+        rd: u8,
+        rn: u8,
+        operand: Operand,
+        setflags: bool,
+    },
+    And {
+        rd: u8,
+        rn: u8,
+        operand: Operand,
+        setflags: bool,
+    },
+    Orr {
+        rd: u8,
+        rn: u8,
+        operand: Operand,
+        setflags: bool,
+    },
+    Eor {
+        rd: u8,
+        rn: u8,
+        operand: Operand,
+        setflags: bool,
+    },
+    Bic {
+        rd: u8,
+        rn: u8,
+        operand: Operand,
+        setflags: bool,
+    },
+    Orn {
+        rd: u8,
+        rn: u8,
+        operand: Operand,
+        setflags: bool,
+    },
+    Cmp {
+        rn: u8,
+        operand: Operand,
+    },
+    Cmn {
+        rn: u8,
+        operand: Operand,
+    },
+    Tst {
+        rn: u8,
+        operand: Operand,
+    },
+    Teq {
+        rn: u8,
+        operand: Operand,
+    },
+    Movt {
+        rd: u8,
+        imm16: u16,
+    },
+    ShiftReg {
+        kind: ShiftKind,
+        rd: u8,
+        rn: u8,
         rm: u8,
+        setflags: bool,
+    },
+    Mul {
+        rd: u8,
         rn: u8,
+        rm: u8,
+        setflags: bool,
+    },
+    Mla {
         rd: u8,
+        rn: u8,
+        rm: u8,
+        ra: u8,
+        subtract: bool,
     },
-    Blx {
+    MulLong {
+        rdlo: u8,
+        rdhi: u8,
+        rn: u8,
         rm: u8,
+        signed: bool,
+        accumulate: bool,
     },
-    Bx {
+    Umaal {
+        rdlo: u8,
+        rdhi: u8,
+        rn: u8,
         rm: u8,
     },
-    B {
-        imm11: i16,
+    Div {
+        rd: u8,
+        rn: u8,
+        rm: u8,
+        signed: bool,
     },
-    Ldr {
-        rt: u8,
-        source: Source,
+    Smla {
+        rd: u8,
+        rn: u8,
+        rm: u8,
+        ra: Option<u8>,
+        n_high: bool,
+        m_high: bool,
+    },
+    Smlaw {
+        rd: u8,
+        rn: u8,
+        rm: u8,
+        ra: Option<u8>,
+        m_high: bool,
+    },
+    Smlad {
+        rd: u8,
+        rn: u8,
+        rm: u8,
+        ra: Option<u8>,
+        exchange: bool,
+        subtract: bool,
+    },
+    Smmla {
+        rd: u8,
+        rn: u8,
+        rm: u8,
+        ra: Option<u8>,
+        round: bool,
+        subtract: bool,
+    },
+    Smlal {
+        rdlo: u8,
+        rdhi: u8,
+        rn: u8,
+        rm: u8,
+        n_high: bool,
+        m_high: bool,
+    },
+    Smlald {
+        rdlo: u8,
+        rdhi: u8,
+        rn: u8,
+        rm: u8,
+        exchange: bool,
+        subtract: bool,
+    },
+    Parallel {
+        op: ParallelOp,
+        prefix: ParallelPrefix,
+        rd: u8,
+        rn: u8,
+        rm: u8,
+    },
+    Qadd {
+        rd: u8,
+        rm: u8,
+        rn: u8,
+        double: bool,
+        subtract: bool,
+    },
+    Sat {
+        rd: u8,
+        saturate_to: u8,
+        operand: Operand,
+        unsigned: bool,
+    },
+    Sat16 {
+        rd: u8,
+        saturate_to: u8,
+        rn: u8,
+        unsigned: bool,
+    },
+    Sel {
+        rd: u8,
+        rn: u8,
+        rm: u8,
     },
-    LdrReg {
+    Usad8 {
+        rd: u8,
+        rn: u8,
         rm: u8,
+        ra: Option<u8>,
+    },
+    Pkh {
+        rd: u8,
         rn: u8,
+        operand: Operand,
+        tb: bool,
+    },
+    Extend {
+        rd: u8,
+        rn: Option<u8>,
+        rm: u8,
+        rotation: u8,
+        width: Width,
+        signed: bool,
+    },
+    Bfi {
+        rd: u8,
+        rn: Option<u8>,
+        lsb: u8,
+        width: u8,
+    },
+    Bfx {
+        rd: u8,
+        rn: u8,
+        lsb: u8,
+        width: u8,
+        signed: bool,
+    },
+    Clz {
+        rd: u8,
+        rm: u8,
+    },
+    Reverse {
+        op: ReverseOp,
+        rd: u8,
+        rm: u8,
+    },
+    Extend16 {
+        rd: u8,
+        rn: Option<u8>,
+        rm: u8,
+        rotation: u8,
+        signed: bool,
+    },
+    Vldr {
+        vd: FpRegister,
+        address: Address,
+    },
+    Vstr {
+        vd: FpRegister,
+        address: Address,
+    },
+    Vldm {
+        rn: u8,
+        vd: FpRegister,
+        count: u8,
+        add: bool,
+        wback: bool,
+    },
+    Vstm {
+        rn: u8,
+        vd: FpRegister,
+        count: u8,
+        add: bool,
+        wback: bool,
+    },
+    VmovCore {
+        sn: u8,
+        rt: u8,
+        to_core: bool,
+    },
+    VmovCorePair {
+        vm: FpRegister,
+        rt: u8,
+        rt2: u8,
+        to_core: bool,
+    },
+    VmovImm {
+        sd: u8,
+        imm32: u32,
+    },
+    Vfp {
+        op: VfpOp,
+        sd: u8,
+        sn: u8,
+        sm: u8,
+    },
+    VfpUnary {
+        op: VfpUnaryOp,
+        sd: u8,
+        sm: u8,
+    },
+    Vcmp {
+        sd: u8,
+        sm: Option<u8>,
+        signal: bool,
+    },
+    VcvtInt {
+        sd: u8,
+        sm: u8,
+        signed: bool,
+        to_int: bool,
+        round_zero: bool,
+    },
+    VcvtFixed {
+        sd: u8,
+        signed: bool,
+        half: bool,
+        fbits: u8,
+        to_fixed: bool,
+    },
+    VcvtHalf {
+        sd: u8,
+        sm: u8,
+        top: bool,
+        to_half: bool,
+    },
+    Vmrs {
+        rt: u8,
+    },
+    Vmsr {
         rt: u8,
     },
-    LdrImm {
-        source: Source,
+    Adr {
+        rd: u8,
+        imm32: u32,
+        add: bool,
+    },
+    B {
+        cond: Condition,
+        imm32: i32,
+    },
+    Bl {
+        imm32: i32,
+    },
+    Bx {
+        rm: u8,
+    },
+    Blx {
+        rm: u8,
+    },
+    Cbz {
         rn: u8,
+        imm32: u32,
+        nonzero: bool,
+    },
+    Tbb {
+        rn: u8,
+        rm: u8,
+        half: bool,
+    },
+    Ldr {
         rt: u8,
+        address: Address,
+        width: Width,
+        signed: bool,
     },
     Str {
         rt: u8,
+        address: Address,
+        width: Width,
+    },
+    Ldrd {
+        rt: u8,
+        rt2: u8,
+        address: Address,
+    },
+    Strd {
+        rt: u8,
+        rt2: u8,
+        address: Address,
+    },
+    Ldrex {
+        rt: u8,
+        address: Address,
+        width: Width,
+    },
+    Strex {
+        rd: u8,
+        rt: u8,
+        address: Address,
+        width: Width,
+    },
+    Clrex,
+    Pld {
+        address: Address,
+    },
+    Ldm {
+        rn: u8,
+        registers: u16,
+        wback: bool,
+    },
+    Ldmdb {
         rn: u8,
+        registers: u16,
+        wback: bool,
+    },
+    Stm {
+        rn: u8,
+        registers: u16,
+        wback: bool,
+    },
+    Stmdb {
+        rn: u8,
+        registers: u16,
+        wback: bool,
+    },
+    Push {
+        registers: u16,
+    },
+    Pop {
+        registers: u16,
+    },
+    It {
+        firstcond: Condition,
+        mask: u8,
+    },
+    Mrs {
+        rd: u8,
+        sysm: u8,
+    },
+    Msr {
+        rn: u8,
+        sysm: u8,
+        mask: u8,
+    },
+    Cps {
+        enable: bool,
+        primask: bool,
+        faultmask: bool,
+    },
+    Svc {
+        imm8: u8,
+    },
+    Bkpt {
+        imm8: u8,
+    },
+    Nop,
+    Yield,
+    Wfe,
+    Wfi,
+    Sev,
+    Dsb {
+        option: u8,
+    },
+    Dmb {
+        option: u8,
+    },
+    Isb {
+        option: u8,
     },
     Undefined,
 }
 
+impl InstructionKind {
+    // table 3.1 @ Cortex-M4 Technical Reference Manual, branch refills are accounted for by the processor
+    pub fn cycles(&self) -> u64 {
+        match self {
+            InstructionKind::Ldr { .. } | InstructionKind::Ldrex { .. } | InstructionKind::Tbb { .. } | InstructionKind::Vldr { .. } | InstructionKind::Vstr { .. } => 2,
+            InstructionKind::Ldrd { .. } | InstructionKind::Strd { .. } => 3,
+            InstructionKind::Ldm { registers, .. } | InstructionKind::Ldmdb { registers, .. } | InstructionKind::Stm { registers, .. }
+                | InstructionKind::Stmdb { registers, .. } => 1 + registers.count_ones() as u64,
+            InstructionKind::Push { registers } | InstructionKind::Pop { registers } => 1 + registers.count_ones() as u64,
+            InstructionKind::Vldm { count, .. } | InstructionKind::Vstm { count, .. } => 1 + *count as u64,
+            // division terminates early on the hardware, this is the worst case
+            InstructionKind::Div { .. } => 12,
+            InstructionKind::Vfp { op: VfpOp::Div, .. } | InstructionKind::VfpUnary { op: VfpUnaryOp::Sqrt, .. } => 14,
+            InstructionKind::Vfp { op: VfpOp::Mla | VfpOp::Mls | VfpOp::Nmla | VfpOp::Nmls | VfpOp::Fma | VfpOp::Fms | VfpOp::Fnma | VfpOp::Fnms, .. } => 3,
+            _ => 1,
+        }
+    }
+}
+
 impl std::fmt::Display for InstructionKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let s = |setflags: &bool| if *setflags { "s" } else { "" };
+        let x = |exchange: &bool| if *exchange { "x" } else { "" };
+        let half = |high: &bool| if *high { "t" } else { "b" };
+        let la = |ra: &Option<u8>| if ra.is_some() { "la" } else { "ul" };
+        let accumulator = |ra: &Option<u8>| ra.map(|ra| format!(", {}", Register(ra))).unwrap_or_default();
+
         match self {
-            InstructionKind::Mov { register, source } => f.write_fmt(format_args!("mov r{}, {}", register, Into::<u32>::into(source.clone()))),
-            InstructionKind::Add { rm, rn, rd } => f.write_fmt(format_args!("add r{}, r{}, r{}", rd, rn, rm)),
-            InstructionKind::Blx { rm } => f.write_fmt(format_args!("blx r{}", rm)),
-            InstructionKind::Bx { rm } => f.write_fmt(format_args!("bx r{}", rm)),
-            InstructionKind::B { imm11 } => f.write_fmt(format_args!("b {}", imm11)),
-            InstructionKind::Ldr { rt, source } => f.write_fmt(format_args!("ldr r{}, ={}", rt, Into::<u32>::into(source.clone()))),
-            InstructionKind::LdrReg { rm, rn, rt } => f.write_fmt(format_args!("ldr r{}, [r{}, r{}]", rt, rn, rm)),
-            InstructionKind::LdrImm { source, rn, rt } => f.write_fmt(format_args!("ldr r{}, [r{}, #{}]", rt, rn, Into::<u32>::into(source.clone()))),
-            InstructionKind::Str { rt, rn } => f.write_fmt(format_args!("str r{}, [r{}]", rt, rn)),
-            InstructionKind::Undefined => f.write_fmt(format_args!("undefined")),
+            InstructionKind::Mov { rd, operand: Operand::Reg { rm, shift }, setflags } if !shift.is_none() => match shift.kind {
+                ShiftKind::Rrx => f.write_fmt(format_args!("rrx{} {}, {}", s(setflags), Register(*rd), Register(*rm))),
+                _ => f.write_fmt(format_args!("{}{} {}, {}, #{}", shift.kind, s(setflags), Register(*rd), Register(*rm), shift.amount)),
+            },
+            InstructionKind::Mov { rd, operand, setflags } => f.write_fmt(format_args!("mov{} {}, {}", s(setflags), Register(*rd), operand)),
+            InstructionKind::Mvn { rd, operand, setflags } => f.write_fmt(format_args!("mvn{} {}, {}", s(setflags), Register(*rd), operand)),
+            InstructionKind::Add { rd, rn, operand, setflags } => f.write_fmt(format_args!("add{} {}, {}, {}", s(setflags), Register(*rd), Register(*rn), operand)),
+            InstructionKind::Adc { rd, rn, operand, setflags } => f.write_fmt(format_args!("adc{} {}, {}, {}", s(setflags), Register(*rd), Register(*rn), operand)),
+            InstructionKind::Sub { rd, rn, operand, setflags } => f.write_fmt(format_args!("sub{} {}, {}, {}", s(setflags), Register(*rd), Register(*rn), operand)),
+            InstructionKind::Sbc { rd, rn, operand, setflags } => f.write_fmt(format_args!("sbc{} {}, {}, {}", s(setflags), Register(*rd), Register(*rn), operand)),
+            InstructionKind::Rsb { rd, rn, operand, setflags } => f.write_fmt(format_args!("rsb{} {}, {}, {}", s(setflags), Register(*rd), Register(*rn), operand)),
+            InstructionKind::And { rd, rn, operand, setflags } => f.write_fmt(format_args!("and{} {}, {}, {}", s(setflags), Register(*rd), Register(*rn), operand)),
+            InstructionKind::Orr { rd, rn, operand, setflags } => f.write_fmt(format_args!("orr{} {}, {}, {}", s(setflags), Register(*rd), Register(*rn), operand)),
+            InstructionKind::Eor { rd, rn, operand, setflags } => f.write_fmt(format_args!("eor{} {}, {}, {}", s(setflags), Register(*rd), Register(*rn), operand)),
+            InstructionKind::Bic { rd, rn, operand, setflags } => f.write_fmt(format_args!("bic{} {}, {}, {}", s(setflags), Register(*rd), Register(*rn), operand)),
+            InstructionKind::Orn { rd, rn, operand, setflags } => f.write_fmt(format_args!("orn{} {}, {}, {}", s(setflags), Register(*rd), Register(*rn), operand)),
+            InstructionKind::Cmp { rn, operand } => f.write_fmt(format_args!("cmp {}, {}", Register(*rn), operand)),
+            InstructionKind::Cmn { rn, operand } => f.write_fmt(format_args!("cmn {}, {}", Register(*rn), operand)),
+            InstructionKind::Tst { rn, operand } => f.write_fmt(format_args!("tst {}, {}", Register(*rn), operand)),
+            InstructionKind::Teq { rn, operand } => f.write_fmt(format_args!("teq {}, {}", Register(*rn), operand)),
+            InstructionKind::Movt { rd, imm16 } => f.write_fmt(format_args!("movt {}, #{}", Register(*rd), imm16)),
+            InstructionKind::ShiftReg { kind, rd, rn, rm, setflags } => f.write_fmt(format_args!("{}{} {}, {}, {}", kind, s(setflags), Register(*rd), Register(*rn), Register(*rm))),
+            InstructionKind::Mul { rd, rn, rm, setflags } => f.write_fmt(format_args!("mul{} {}, {}, {}", s(setflags), Register(*rd), Register(*rn), Register(*rm))),
+            InstructionKind::Mla { rd, rn, rm, ra, subtract } => {
+                f.write_fmt(format_args!("ml{} {}, {}, {}, {}", if *subtract { "s" } else { "a" }, Register(*rd), Register(*rn), Register(*rm), Register(*ra)))
+            },
+            InstructionKind::MulLong { rdlo, rdhi, rn, rm, signed, accumulate } => {
+                f.write_fmt(format_args!("{}m{}l {}, {}, {}, {}", if *signed { "s" } else { "u" }, if *accumulate { "la" } else { "ul" }, Register(*rdlo), Register(*rdhi), Register(*rn), Register(*rm)))
+            },
+            InstructionKind::Umaal { rdlo, rdhi, rn, rm } => f.write_fmt(format_args!("umaal {}, {}, {}, {}", Register(*rdlo), Register(*rdhi), Register(*rn), Register(*rm))),
+            InstructionKind::Div { rd, rn, rm, signed } => f.write_fmt(format_args!("{}div {}, {}, {}", if *signed { "s" } else { "u" }, Register(*rd), Register(*rn), Register(*rm))),
+            InstructionKind::Smla { rd, rn, rm, ra, n_high, m_high } => {
+                f.write_fmt(format_args!("sm{}{}{} {}, {}, {}{}", la(ra), half(n_high), half(m_high), Register(*rd), Register(*rn), Register(*rm), accumulator(ra)))
+            },
+            InstructionKind::Smlaw { rd, rn, rm, ra, m_high } => {
+                f.write_fmt(format_args!("sm{}w{} {}, {}, {}{}", la(ra), half(m_high), Register(*rd), Register(*rn), Register(*rm), accumulator(ra)))
+            },
+            InstructionKind::Smlad { rd, rn, rm, ra, exchange, subtract } => {
+                let (prefix, op) = (if ra.is_some() { "sml" } else { "smu" }, if *subtract { "s" } else { "a" });
+
+                f.write_fmt(format_args!("{}{}d{} {}, {}, {}{}", prefix, op, x(exchange), Register(*rd), Register(*rn), Register(*rm), accumulator(ra)))
+            },
+            InstructionKind::Smmla { rd, rn, rm, ra, round, subtract } => {
+                let op = match (ra, subtract) {
+                    (_, true) => "mls",
+                    (Some(_), false) => "mla",
+                    (None, false) => "mul",
+                };
+
+                f.write_fmt(format_args!("sm{}{} {}, {}, {}{}", op, if *round { "r" } else { "" }, Register(*rd), Register(*rn), Register(*rm), accumulator(ra)))
+            },
+            InstructionKind::Smlal { rdlo, rdhi, rn, rm, n_high, m_high } => {
+                f.write_fmt(format_args!("smlal{}{} {}, {}, {}, {}", half(n_high), half(m_high), Register(*rdlo), Register(*rdhi), Register(*rn), Register(*rm)))
+            },
+            InstructionKind::Smlald { rdlo, rdhi, rn, rm, exchange, subtract } => {
+                f.write_fmt(format_args!("sml{}ld{} {}, {}, {}, {}", if *subtract { "s" } else { "a" }, x(exchange), Register(*rdlo), Register(*rdhi), Register(*rn), Register(*rm)))
+            },
+            InstructionKind::Parallel { op, prefix, rd, rn, rm } => f.write_fmt(format_args!("{}{} {}, {}, {}", prefix, op, Register(*rd), Register(*rn), Register(*rm))),
+            InstructionKind::Qadd { rd, rm, rn, double, subtract } => {
+                f.write_fmt(format_args!("q{}{} {}, {}, {}", if *double { "d" } else { "" }, if *subtract { "sub" } else { "add" }, Register(*rd), Register(*rm), Register(*rn)))
+            },
+            InstructionKind::Sat { rd, saturate_to, operand, unsigned } => {
+                f.write_fmt(format_args!("{}sat {}, #{}, {}", if *unsigned { "u" } else { "s" }, Register(*rd), saturate_to, operand))
+            },
+            InstructionKind::Sat16 { rd, saturate_to, rn, unsigned } => {
+                f.write_fmt(format_args!("{}sat16 {}, #{}, {}", if *unsigned { "u" } else { "s" }, Register(*rd), saturate_to, Register(*rn)))
+            },
+            InstructionKind::Sel { rd, rn, rm } => f.write_fmt(format_args!("sel {}, {}, {}", Register(*rd), Register(*rn), Register(*rm))),
+            InstructionKind::Usad8 { rd, rn, rm, ra } => {
+                f.write_fmt(format_args!("usad{}8 {}, {}, {}{}", if ra.is_some() { "a" } else { "" }, Register(*rd), Register(*rn), Register(*rm), accumulator(ra)))
+            },
+            InstructionKind::Pkh { rd, rn, operand, tb } => {
+                f.write_fmt(format_args!("pkh{} {}, {}, {}", if *tb { "tb" } else { "bt" }, Register(*rd), Register(*rn), operand))
+            },
+            InstructionKind::Extend { rd, rn, rm, rotation, width, signed } => {
+                let (add, rn) = (if rn.is_some() { "a" } else { "" }, rn.map(|rn| format!("{}, ", Register(rn))).unwrap_or_default());
+                let rotation = if *rotation != 0 { format!(", ror #{}", rotation) } else { String::new() };
+
+                f.write_fmt(format_args!("{}xt{}{} {}, {}{}{}", if *signed { "s" } else { "u" }, add, width, Register(*rd), rn, Register(*rm), rotation))
+            },
+            InstructionKind::Bfi { rd, rn: Some(rn), lsb, width } => f.write_fmt(format_args!("bfi {}, {}, #{}, #{}", Register(*rd), Register(*rn), lsb, width)),
+            InstructionKind::Bfi { rd, rn: None, lsb, width } => f.write_fmt(format_args!("bfc {}, #{}, #{}", Register(*rd), lsb, width)),
+            InstructionKind::Bfx { rd, rn, lsb, width, signed } => f.write_fmt(format_args!("{}bfx {}, {}, #{}, #{}", if *signed { "s" } else { "u" }, Register(*rd), Register(*rn), lsb, width)),
+            InstructionKind::Clz { rd, rm } => f.write_fmt(format_args!("clz {}, {}", Register(*rd), Register(*rm))),
+            InstructionKind::Reverse { op, rd, rm } => f.write_fmt(format_args!("{} {}, {}", op, Register(*rd), Register(*rm))),
+            InstructionKind::Extend16 { rd, rn, rm, rotation, signed } => {
+                let (add, rn) = (if rn.is_some() { "a" } else { "" }, rn.map(|rn| format!("{}, ", Register(rn))).unwrap_or_default());
+                let rotation = if *rotation != 0 { format!(", ror #{}", rotation) } else { String::new() };
+
+                f.write_fmt(format_args!("{}xt{}b16 {}, {}{}{}", if *signed { "s" } else { "u" }, add, Register(*rd), rn, Register(*rm), rotation))
+            },
+            InstructionKind::Vldr { vd, address } => f.write_fmt(format_args!("vldr {}, {}", vd, address)),
+            InstructionKind::Vstr { vd, address } => f.write_fmt(format_args!("vstr {}, {}", vd, address)),
+            InstructionKind::Vldm { rn: 13, vd, count, add: true, wback: true } => f.write_fmt(format_args!("vpop {}", FpRegisterList(*vd, *count))),
+            InstructionKind::Vstm { rn: 13, vd, count, add: false, wback: true } => f.write_fmt(format_args!("vpush {}", FpRegisterList(*vd, *count))),
+            InstructionKind::Vldm { rn, vd, count, add, wback } | InstructionKind::Vstm { rn, vd, count, add, wback } => {
+                let mnemonic = if matches!(self, InstructionKind::Vldm { .. }) { "vldm" } else { "vstm" };
+
+                f.write_fmt(format_args!("{}{} {}{}, {}", mnemonic, if *add { "ia" } else { "db" }, Register(*rn), if *wback { "!" } else { "" }, FpRegisterList(*vd, *count)))
+            },
+            InstructionKind::VmovCore { sn, rt, to_core: true } => f.write_fmt(format_args!("vmov {}, {}", Register(*rt), FpRegister::single(*sn))),
+            InstructionKind::VmovCore { sn, rt, to_core: false } => f.write_fmt(format_args!("vmov {}, {}", FpRegister::single(*sn), Register(*rt))),
+            InstructionKind::VmovCorePair { vm, rt, rt2, to_core } => {
+                let vm = match vm.double {
+                    true => vm.to_string(),
+                    false => format!("{}, {}", vm, FpRegister::single(vm.index + 1)),
+                };
+
+                match to_core {
+                    true => f.write_fmt(format_args!("vmov {}, {}, {}", Register(*rt), Register(*rt2), vm)),
+                    false => f.write_fmt(format_args!("vmov {}, {}, {}", vm, Register(*rt), Register(*rt2))),
+                }
+            },
+            InstructionKind::VmovImm { sd, imm32 } => f.write_fmt(format_args!("vmov.f32 {}, #{:?}", FpRegister::single(*sd), f32::from_bits(*imm32))),
+            InstructionKind::Vfp { op, sd, sn, sm } => {
+                f.write_fmt(format_args!("{}.f32 {}, {}, {}", op, FpRegister::single(*sd), FpRegister::single(*sn), FpRegister::single(*sm)))
+            },
+            InstructionKind::VfpUnary { op, sd, sm } => f.write_fmt(format_args!("{}.f32 {}, {}", op, FpRegister::single(*sd), FpRegister::single(*sm))),
+            InstructionKind::Vcmp { sd, sm, signal } => {
+                let sm = sm.map(|sm| FpRegister::single(sm).to_string()).unwrap_or(String::from("#0.0"));
+
+                f.write_fmt(format_args!("vcmp{}.f32 {}, {}", if *signal { "e" } else { "" }, FpRegister::single(*sd), sm))
+            },
+            InstructionKind::VcvtInt { sd, sm, signed, to_int, round_zero } => {
+                let int = if *signed { "s32" } else { "u32" };
+
+                match to_int {
+                    true => f.write_fmt(format_args!("vcvt{}.{}.f32 {}, {}", if *round_zero { "" } else { "r" }, int, FpRegister::single(*sd), FpRegister::single(*sm))),
+                    false => f.write_fmt(format_args!("vcvt.f32.{} {}, {}", int, FpRegister::single(*sd), FpRegister::single(*sm))),
+                }
+            },
+            InstructionKind::VcvtFixed { sd, signed, half, fbits, to_fixed } => {
+                let fixed = format!("{}{}", if *signed { "s" } else { "u" }, if *half { 16 } else { 32 });
+                let (to, from) = if *to_fixed { (fixed.as_str(), "f32") } else { ("f32", fixed.as_str()) };
+
+                f.write_fmt(format_args!("vcvt.{}.{} {}, {}, #{}", to, from, FpRegister::single(*sd), FpRegister::single(*sd), fbits))
+            },
+            InstructionKind::VcvtHalf { sd, sm, top, to_half } => {
+                let (to, from) = if *to_half { ("f16", "f32") } else { ("f32", "f16") };
+
+                f.write_fmt(format_args!("vcvt{}.{}.{} {}, {}", if *top { "t" } else { "b" }, to, from, FpRegister::single(*sd), FpRegister::single(*sm)))
+            },
+            InstructionKind::Vmrs { rt: 15 } => f.write_str("vmrs APSR_nzcv, fpscr"),
+            InstructionKind::Vmrs { rt } => f.write_fmt(format_args!("vmrs {}, fpscr", Register(*rt))),
+            InstructionKind::Vmsr { rt } => f.write_fmt(format_args!("vmsr fpscr, {}", Register(*rt))),
+            InstructionKind::Adr { rd, imm32, add } => f.write_fmt(format_args!("adr {}, #{}{}", Register(*rd), if *add { "" } else { "-" }, imm32)),
+            InstructionKind::B { cond, imm32 } => f.write_fmt(format_args!("b{} {}", cond, imm32)),
+            InstructionKind::Bl { imm32 } => f.write_fmt(format_args!("bl {}", imm32)),
+            InstructionKind::Bx { rm } => f.write_fmt(format_args!("bx {}", Register(*rm))),
+            InstructionKind::Blx { rm } => f.write_fmt(format_args!("blx {}", Register(*rm))),
+            InstructionKind::Cbz { rn, imm32, nonzero } => f.write_fmt(format_args!("cb{}z {}, {}", if *nonzero { "n" } else { "" }, Register(*rn), imm32)),
+            InstructionKind::Tbb { rn, rm, half: false } => f.write_fmt(format_args!("tbb [{}, {}]", Register(*rn), Register(*rm))),
+            InstructionKind::Tbb { rn, rm, half: true } => f.write_fmt(format_args!("tbh [{}, {}, lsl #1]", Register(*rn), Register(*rm))),
+            InstructionKind::Ldrex { rt, address, width } => f.write_fmt(format_args!("ldrex{} {}, {}", width, Register(*rt), address)),
+            InstructionKind::Strex { rd, rt, address, width } => f.write_fmt(format_args!("strex{} {}, {}, {}", width, Register(*rd), Register(*rt), address)),
+            InstructionKind::Clrex => f.write_str("clrex"),
+            InstructionKind::Ldr { rt, address, width, signed } => f.write_fmt(format_args!("ldr{}{} {}, {}", if *signed { "s" } else { "" }, width, Register(*rt), address)),
+            InstructionKind::Str { rt, address, width } => f.write_fmt(format_args!("str{} {}, {}", width, Register(*rt), address)),
+            InstructionKind::Ldrd { rt, rt2, address } => f.write_fmt(format_args!("ldrd {}, {}, {}", Register(*rt), Register(*rt2), address)),
+            InstructionKind::Strd { rt, rt2, address } => f.write_fmt(format_args!("strd {}, {}, {}", Register(*rt), Register(*rt2), address)),
+            InstructionKind::Pld { address } => f.write_fmt(format_args!("pld {}", address)),
+            InstructionKind::Ldm { rn, registers, wback } => f.write_fmt(format_args!("ldm {}{}, {}", Register(*rn), if *wback { "!" } else { "" }, RegisterList(*registers))),
+            InstructionKind::Ldmdb { rn, registers, wback } => f.write_fmt(format_args!("ldmdb {}{}, {}", Register(*rn), if *wback { "!" } else { "" }, RegisterList(*registers))),
+            InstructionKind::Stmdb { rn, registers, wback } => f.write_fmt(format_args!("stmdb {}{}, {}", Register(*rn), if *wback { "!" } else { "" }, RegisterList(*registers))),
+            InstructionKind::Stm { rn, registers, wback } => f.write_fmt(format_args!("stm {}{}, {}", Register(*rn), if *wback { "!" } else { "" }, RegisterList(*registers))),
+            InstructionKind::Push { registers } => f.write_fmt(format_args!("push {}", RegisterList(*registers))),
+            InstructionKind::Pop { registers } => f.write_fmt(format_args!("pop {}", RegisterList(*registers))),
+            InstructionKind::It { firstcond, mask } => {
+                let suffix = (mask.trailing_zeros() + 1..4)
+                    .rev()
+                    .map(|bit| if (mask >> bit) & 1 == *firstcond as u8 & 1 { 't' } else { 'e' })
+                    .collect::<String>();
+
+                f.write_fmt(format_args!("it{} {}", suffix, firstcond))
+            },
+            InstructionKind::Svc { imm8 } => f.write_fmt(format_args!("svc {}", imm8)),
+            InstructionKind::Bkpt { imm8 } => f.write_fmt(format_args!("bkpt {:#06x}", imm8)),
+            InstructionKind::Nop => f.write_str("nop"),
+            InstructionKind::Yield => f.write_str("yield"),
+            InstructionKind::Wfe => f.write_str("wfe"),
+            InstructionKind::Wfi => f.write_str("wfi"),
+            InstructionKind::Sev => f.write_str("sev"),
+            InstructionKind::Mrs { rd, sysm } => f.write_fmt(format_args!("mrs {}, {}", Register(*rd), SpecialRegister(*sysm))),
+            InstructionKind::Msr { rn, sysm, mask } => {
+                let suffix = match (*sysm < 8, mask) {
+                    (true, 0b01) => "_g",
+                    (true, 0b10) => "_nzcvq",
+                    (true, 0b11) => "_nzcvqg",
+                    _ => "",
+                };
+
+                f.write_fmt(format_args!("msr {}{}, {}", SpecialRegister(*sysm), suffix, Register(*rn)))
+            },
+            InstructionKind::Cps { enable, primask, faultmask } => {
+                f.write_fmt(format_args!("cps{} {}{}", if *enable { "ie" } else { "id" }, if *primask { "i" } else { "" }, if *faultmask { "f" } else { "" }))
+            },
+            InstructionKind::Dsb { option } => f.write_fmt(format_args!("dsb {}", Barrier(*option))),
+            InstructionKind::Dmb { option } => f.write_fmt(format_args!("dmb {}", Barrier(*option))),
+            InstructionKind::Isb { option } => f.write_fmt(format_args!("isb {}", Barrier(*option))),
+            InstructionKind::Undefined => f.write_str("undefined"),
         }
     }
 }
@@ -78,9 +1169,19 @@ impl std::fmt::Display for InstructionKind {
 #[derive(Debug, Clone)]
 pub struct Instruction {
     pub kind: InstructionKind,
+    pub condition: Condition,
     pub addr: u32,
     pub size: u32,
 }
 
+// instructions inside an it block carry the condition as a suffix on the mnemonic
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = self.kind.to_string();
 
-
+        match text.split_once(' ') {
+            Some((mnemonic, operands)) => f.write_fmt(format_args!("{}{} {}", mnemonic, self.condition, operands)),
+            None => f.write_fmt(format_args!("{}{}", text, self.condition)),
+        }
+    }
+}
@@ -2,41 +2,134 @@ pub mod instruction;
 pub mod registers;
 mod decoder;
 mod fault;
+mod alu;
+mod fpu;
+mod scb;
+mod systick;
 
-use crate::bus::{DataBus, BitSize};
+use crate::bus::{DataBus, BitSize, Bus, Peripheral};
 use crate::memory::Memory;
+use crate::machine::{Machine, Alias};
 
-use instruction::{Instruction, InstructionKind};
-use registers::Registers;
-use decoder::Decoder;
-use fault::{InterruptController, Exception};
+use instruction::{Instruction, InstructionKind, Operand, Offset, Address, Width, ReverseOp};
+use registers::{Registers, Flag};
+use decoder::{Decoder, SignExtend};
+use alu::{add_with_carry, shift_c, half, signed_sat_q, unsigned_sat_q, parallel};
+use fault::{InterruptController, Exception, Fault, LOCKUP_ADDRESS, ENTRY_CYCLES, TAIL_CHAIN_CYCLES};
+use systick::SysTick;
+
+pub use fault::IRQ_COUNT;
 use object::{File, Object, ObjectSection, SectionKind};
 
-pub const RAM_CAPACITY: usize = 16380;
-pub const FLASH_CAPACITY: usize = 65540;
+use std::ops::Range;
+
+// the nrf52833 implements the upper three bits of every priority field
+pub const PRIORITY_MASK: u8 = 0xe0;
 
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Mode {
     Thread,
     Handle,
 }
 
+// what to do when execution reaches an undefined instruction
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UndefinedPolicy {
+    Fault,
+    Stop,
+}
+
+// why the processor refused to make progress, handed back to the frontend
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stop {
+    Undefined {
+        addr: u32,
+        opcode: u32,
+    },
+    Lockup,
+}
+
+impl std::fmt::Display for Stop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Stop::Undefined { addr, opcode } => f.write_fmt(format_args!("undefined instruction {:#x} at {:#x}", opcode, addr)),
+            Stop::Lockup => f.write_fmt(format_args!("locked up at {:#x}", LOCKUP_ADDRESS)),
+        }
+    }
+}
+
+// what a sleeping processor waits for, sleep on exit keeps the exc_return of the frame it left stacked
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sleep {
+    Interrupt,
+    Event,
+    Exit(u32),
+}
+
 #[derive(Clone)]
 pub struct Processor {
-    flash: Memory,
-    ram: Memory,
+    memory: Vec<Memory>,
+    alias: Vec<Alias>,
     nvic: InterruptController,
+    systick: SysTick,
+    bus: Bus,
+    branch: bool,
+    faulted: bool,
+    exclusive: Option<u32>,
+    bus_error: Option<u32>,
+    lockup: bool,
+    sleep: Option<Sleep>,
+    vtor: u32,
+    pub cycles: u64,
+    pub undefined: UndefinedPolicy,
     pub mode: Mode,
     pub registers: Registers,
 }
 
+impl Default for Processor {
+    fn default() -> Processor {
+        Processor::new()
+    }
+}
+
 impl Processor {
     pub fn new() -> Processor {
+        Processor::with_machine(&Machine::default())
+    }
+
+    pub fn with_machine(machine: &Machine) -> Processor {
+        let memory = machine.memory.iter()
+            .map(|region| {
+                let mut memory = Memory::new(region.base as usize, region.size as usize, region.permissions);
+
+                memory.load(&region.contents);
+
+                memory
+            })
+            .collect::<Vec<Memory>>();
+
+        let mut bus = Bus::new();
+
+        for peripheral in machine.peripheral.iter() {
+            bus.attach(peripheral.base as usize..(peripheral.base + peripheral.size) as usize, peripheral.irq, peripheral.build());
+        }
+
         Processor {
-            flash: Memory::new(0x0, FLASH_CAPACITY),
-            ram: Memory::new(0x20000000, RAM_CAPACITY),
+            memory,
+            alias: machine.alias.clone(),
             nvic: InterruptController::new(),
+            systick: SysTick::new(),
+            bus,
+            branch: false,
+            faulted: false,
+            exclusive: None,
+            bus_error: None,
+            lockup: false,
+            sleep: None,
+            vtor: machine.vtor,
+            cycles: 0,
+            undefined: UndefinedPolicy::Fault,
             mode: Mode::Thread,
             registers: Registers::new(),
         }
@@ -50,19 +143,46 @@ impl Processor {
         self.registers.sp.psp = 0;
 
         self.registers.set(15, |_| handler, self.mode);
+
+        if (handler & 1) != 0 {
+            self.registers.psr.set(24);
+        }
     }
 
     pub fn reset(&mut self) {
         self.registers = Registers::new();
 
+        self.registers.vtor.write(self.vtor);
+
+        self.nvic = InterruptController::new();
+        self.systick = SysTick::new();
+
         self.mode = Mode::Thread;
+        self.lockup = false;
+        self.sleep = None;
 
         self.load_vtor(4);
     }
 
+    // registers a device model on the system bus, the irq is the external interrupt number its line drives
+    pub fn attach(&mut self, range: Range<usize>, irq: Option<usize>, peripheral: Box<dyn Peripheral>) {
+        self.bus.attach(range, irq, peripheral);
+    }
+
+    // aliased addresses resolve to the memory they mirror
+    fn translate(&self, addr: usize) -> usize {
+        self.alias.iter().find_map(|alias| alias.translate(addr)).unwrap_or(addr)
+    }
+
+    // loading an image ignores the permissions of the memory it is written to
     pub fn flash_data(&mut self, addr: usize, data: &[u8]) {
         for (offset, byte) in data.iter().enumerate() {
-            self.write::<u8>(addr as usize + offset, *byte);
+            let physical = self.translate(addr + offset);
+
+            match self.memory.iter_mut().find(|memory| memory.contains(physical, 1)) {
+                Some(memory) => memory.write::<u8>(physical, *byte),
+                None => self.write::<u8>(addr + offset, *byte),
+            }
         }
     }
 
@@ -81,120 +201,2999 @@ impl Processor {
         Ok(())
     }
 
+    // instructions can only be fetched from executable memory
+    fn code(&mut self, addr: usize) -> u16 {
+        let physical = self.translate(addr);
+
+        match self.memory.iter_mut().find(|memory| memory.contains(physical, 2)) {
+            Some(memory) if memory.permissions.execute => memory.read::<u16>(physical),
+            _ => { self.bus_error = Some(addr as u32); 0 },
+        }
+    }
+
     pub fn fetch(&mut self) -> Instruction {
-        match Decoder::new(self.read::<u16>(self.registers.get(15, self.mode) as usize - 4)) {
+        let (it, addr) = (self.registers.psr.in_it_block(), self.registers.pc());
+
+        match Decoder::new(self.code(addr as usize), it) {
             Decoder::Thumb16(thumb16) => {
                 Instruction {
                     kind: thumb16.decode(),
-                    addr: self.registers.get(15, self.mode) - 4,
+                    condition: self.registers.psr.it_condition(),
+                    addr,
                     size: 2,
                 }
             },
             Decoder::Thumb32(thumb32) => {
                 Instruction {
-                    kind: thumb32.decode(self.read::<u16>(self.registers.get(15, self.mode) as usize - 2)),
-                    addr: self.registers.get(15, self.mode) - 8,
+                    kind: thumb32.decode(self.code(addr.wrapping_add(2) as usize)),
+                    condition: self.registers.psr.it_condition(),
+                    addr,
                     size: 4,
                 }
             },
         }
     }
 
-    fn execute(&mut self) {
-        let inst = self.fetch();
+    // steps over an instruction without executing it, keeping the it state in sync
+    pub fn skip(&mut self, inst: &Instruction) {
+        self.registers.psr.it_advance();
 
-        match inst.kind {
-            InstructionKind::Mov { register, source } => {
-                self.registers.set(register, |_| source.into(), self.mode);
-            },
-            InstructionKind::Add { rm, rn, rd } => {
-                let result = self.registers.get(rm, self.mode) + self.registers.get(rn, self.mode);
+        if let InstructionKind::It { firstcond, mask } = inst.kind {
+            self.registers.psr.set_it_state(((firstcond as u8) << 4) | mask);
+        }
 
-                self.registers.set(rd, |_| result, self.mode);
+        self.registers.set(15, |pc| pc.wrapping_add(inst.size), self.mode);
+    }
+
+    fn update_flags(&mut self, result: u32, carry: bool, overflow: Option<bool>) {
+        self.registers.psr.set_flag(Flag::N, result & (1 << 31) != 0);
+        self.registers.psr.set_flag(Flag::Z, result == 0);
+        self.registers.psr.set_flag(Flag::C, carry);
+
+        if let Some(overflow) = overflow {
+            self.registers.psr.set_flag(Flag::V, overflow);
+        }
+    }
+
+    fn shifter(&self, operand: Operand) -> (u32, bool) {
+        let carry = self.registers.psr.flag(Flag::C);
+
+        match operand {
+            Operand::Imm { imm32, carry: imm_carry } => (imm32, imm_carry.unwrap_or(carry)),
+            Operand::Reg { rm, shift } => shift_c(self.registers.get(rm, self.mode), shift.kind, shift.amount as u32, carry),
+        }
+    }
+
+    fn branch_write_pc(&mut self, addr: u32) {
+        self.registers.set(15, |_| addr, self.mode);
+
+        self.branch = true;
+    }
+
+    fn bx_write_pc(&mut self, addr: u32) {
+        if self.mode == Mode::Handle && addr & 0xf0000000 == 0xf0000000 {
+            self.exception_return(addr & !(0xf0000000));
+        } else {
+            self.registers.psr.write(24, addr & 1 != 0);
+
+            self.registers.set(15, |_| addr, self.mode);
+        }
+
+        self.branch = true;
+    }
+
+    fn write_result(&mut self, rd: u8, result: u32, setflags: bool, carry: bool, overflow: Option<bool>) {
+        if rd == 15 {
+            self.branch_write_pc(result);
+        } else {
+            self.registers.set(rd, |_| result, self.mode);
+
+            if setflags {
+                self.update_flags(result, carry, overflow);
+            }
+        }
+    }
+
+    // synchronous faults return to the faulting instruction rather than the next one
+    fn fault(&mut self, fault: Fault) {
+        self.record_fault(fault);
+
+        self.branch = true;
+        self.faulted = true;
+    }
+
+    pub fn privileged(&self) -> bool {
+        self.mode == Mode::Handle || !self.registers.control.private
+    }
+
+    // faultmask can only be written, set or cleared, while the execution priority is above -1
+    fn faultmask_allowed(&self) -> bool {
+        self.execution_priority() > -1
+    }
+
+    // page 520 @ ARMv7M Reference Manual
+    fn read_special(&self, sysm: u8) -> u32 {
+        let privileged = self.privileged();
+
+        match (sysm >> 3, sysm & 0b111) {
+            (0b00000, bits) => {
+                let ipsr = if bits & 0b001 != 0 { self.registers.psr.value & 0x1ff } else { 0 };
+                let apsr = if bits & 0b100 == 0 { self.registers.psr.value & 0xf80f0000 } else { 0 };
+
+                ipsr | apsr
             },
-            InstructionKind::Blx { rm } => {
-                self.nvic.throw(Exception::UsageFault);
+            (0b00001, 0b000) if privileged => self.registers.sp.msp,
+            (0b00001, 0b001) if privileged => self.registers.sp.psp,
+            (0b00010, 0b000) if privileged => self.registers.primask as u32,
+            (0b00010, 0b001 | 0b010) if privileged => self.registers.basepri as u32,
+            (0b00010, 0b011) if privileged => self.registers.faultmask as u32,
+            (0b00010, 0b100) => {
+                let control = self.registers.control;
+
+                ((control.fpca as u32) << 2) | ((control.stack as u32) << 1) | control.private as u32
             },
-            InstructionKind::Bx { rm } => {
-                let addr = self.registers.get(rm, self.mode);
+            _ => 0,
+        }
+    }
 
-                if addr & 0xf0000000 == 0xf0000000 {
-                    self.exception_return(addr & !(0xf0000000));
-                } else {
-                    self.registers.set(15, |_| addr, self.mode);
+    // page 524 @ ARMv7M Reference Manual
+    fn write_special(&mut self, sysm: u8, mask: u8, value: u32) {
+        let privileged = self.privileged();
+
+        match (sysm >> 3, sysm & 0b111) {
+            (0b00000, bits) if bits & 0b100 == 0 => {
+                if mask & 0b10 != 0 {
+                    self.registers.psr.value = (self.registers.psr.value & !0xf8000000) | (value & 0xf8000000);
+                }
+
+                if mask & 0b01 != 0 {
+                    self.registers.psr.set_ge((value >> 16) as u8 & 0xf);
                 }
             },
-            InstructionKind::B { imm11 } => {
-                self.registers.set(15, |pc| (pc as i16 + imm11) as u32, self.mode);
-            },
-            InstructionKind::Ldr { rt, source } => {
-                let pc = 4 * (self.registers.get(15, self.mode) / 4);
-                let data = self.read::<u32>((pc + Into::<u32>::into(source)) as usize);
+            (0b00001, 0b000) if privileged => self.registers.sp.msp = value & !0b11,
+            (0b00001, 0b001) if privileged => self.registers.sp.psp = value & !0b11,
+            (0b00010, 0b000) if privileged => self.registers.primask = value & 1 != 0,
+            (0b00010, 0b001) if privileged => self.registers.basepri = value as u8 & PRIORITY_MASK,
+            (0b00010, 0b010) if privileged => {
+                let basepri = value as u8 & PRIORITY_MASK;
 
-                self.registers.set(rt, |_| data as u32, self.mode);
+                if basepri != 0 && (basepri < self.registers.basepri || self.registers.basepri == 0) {
+                    self.registers.basepri = basepri;
+                }
             },
-            InstructionKind::LdrReg { rm, rn, rt } => {
-                // TODO: implement ldr (register)
+            (0b00010, 0b011) if privileged && self.faultmask_allowed() => self.registers.faultmask = value & 1 != 0,
+            (0b00010, 0b100) if privileged => {
+                self.registers.control.private = value & 0b001 != 0;
+                self.registers.control.fpca = value & 0b100 != 0;
+
+                // the stack pointer selection is fixed to the main stack in handler mode
+                if self.mode == Mode::Thread {
+                    self.registers.control.stack = value & 0b010 != 0;
+                }
             },
-            InstructionKind::LdrImm { source, rn, rt } => {
-                let addr = self.registers.get(rn, self.mode) + Into::<u32>::into(source);
+            _ => {},
+        }
+    }
 
-                let data = self.read::<u32>(addr as usize);
+    fn long(&self, rdlo: u8, rdhi: u8) -> u64 {
+        ((self.registers.get(rdhi, self.mode) as u64) << 32) | self.registers.get(rdlo, self.mode) as u64
+    }
 
-                self.registers.set(rt, |_| data, self.mode);
-            },
-            InstructionKind::Str { rt, rn } => {
-                let value = self.registers.get(rt, self.mode);
-                let addr = self.registers.get(rn, self.mode);
+    fn write_long(&mut self, rdlo: u8, rdhi: u8, result: u64) {
+        self.registers.set(rdlo, |_| result as u32, self.mode);
+        self.registers.set(rdhi, |_| (result >> 32) as u32, self.mode);
+    }
+
+    fn accumulator(&self, ra: Option<u8>) -> i64 {
+        ra.map(|ra| self.registers.get(ra, self.mode) as i32 as i64).unwrap_or(0)
+    }
 
-                // println!("addr: {}, value: {}", addr, value);
+    // the dsp multiplies wrap the result and set the sticky q flag on signed overflow
+    fn write_q(&mut self, rd: u8, result: i64) {
+        if result != result as i32 as i64 {
+            self.registers.psr.set_flag(Flag::Q, true);
+        }
 
-                self.write::<u32>(addr as usize, value);
-            },
-            InstructionKind::Undefined => panic!("undefined behaviour"),
+        self.registers.set(rd, |_| result as u32, self.mode);
+    }
+
+    fn address(&self, address: Address) -> (u32, u32) {
+        let base = match address.rn {
+            15 => self.registers.get(15, self.mode) & !3,
+            rn => self.registers.get(rn, self.mode),
+        };
+
+        let offset = match address.offset {
+            Offset::Imm(imm32) => imm32,
+            Offset::Reg { rm, shift } => self.registers.get(rm, self.mode) << shift,
+        };
+
+        let offset_addr = if address.add { base.wrapping_add(offset) } else { base.wrapping_sub(offset) };
+
+        (if address.index { offset_addr } else { base }, offset_addr)
+    }
+
+    // multi-word and exclusive accesses must always be aligned, single loads and stores only when ccr.unalign_trp is set
+    fn aligned(&mut self, addr: u32, size: u32, strict: bool) -> bool {
+        let aligned = addr & (size - 1) == 0 || !(strict || self.nvic.scb.ccr.unalign_trp());
+
+        if !aligned {
+            self.fault(Fault::Unaligned);
         }
 
-        self.registers.set(15, |pc| pc + inst.size, self.mode);
+        aligned
     }
 
-    fn handle_exception(&mut self) {
-        if let Some(exception) = self.nvic.poll() {
-            match exception {
-                Exception::Reset => self.reset(),
-                _ => {
-                    self.push_stack();
+    fn load(&mut self, addr: u32, width: Width, signed: bool) -> u32 {
+        match (width, signed) {
+            (Width::Byte, false) => self.read::<u8>(addr as usize) as u32,
+            (Width::Byte, true) => (self.read::<u8>(addr as usize) as u32).extend(8) as u32,
+            (Width::Halfword, false) => self.read::<u16>(addr as usize) as u32,
+            (Width::Halfword, true) => (self.read::<u16>(addr as usize) as u32).extend(16) as u32,
+            (Width::Word, _) => self.read::<u32>(addr as usize),
+        }
+    }
 
-                    self.exception_entry(exception);
-                },
-            }
+    fn store(&mut self, addr: u32, width: Width, value: u32) {
+        match width {
+            Width::Byte => self.write::<u8>(addr as usize, value as u8),
+            Width::Halfword => self.write::<u16>(addr as usize, value as u16),
+            Width::Word => self.write::<u32>(addr as usize, value),
         }
     }
 
-    pub fn step(&mut self) {
-        self.execute();
+    // every word is read before any register changes so a precise bus fault leaves the registers untouched
+    fn load_multiple(&mut self, addr: u32, registers: u16) -> Option<Vec<(u8, u32)>> {
+        let values: Vec<(u8, u32)> = (0..16)
+            .filter(|register| registers & (1 << register) != 0)
+            .enumerate()
+            .map(|(index, register)| (register, self.read::<u32>(addr.wrapping_add(index as u32 * 4) as usize)))
+            .collect();
 
-        self.handle_exception();
+        self.bus_error.is_none().then_some(values)
     }
-}
 
-impl DataBus for Processor {
-    fn read<T>(&mut self, addr: usize) -> T where T: BitSize + Default {
-        match addr {
-            0x0..0x10004 => self.flash.read(addr),
-            0x20000000..0x20003ffc => self.ram.read(addr),
-            0xe000ed08 => self.registers.vtor.read(),
-            _ => { self.nvic.throw(Exception::BusFault); T::default() },
+    fn commit_multiple(&mut self, values: Vec<(u8, u32)>) {
+        for (register, value) in values {
+            match register {
+                15 => self.bx_write_pc(value),
+                _ => self.registers.set(register, |_| value, self.mode),
+            }
         }
     }
 
-    fn write<T>(&mut self, addr: usize, value: T) where u32: From<T>, T: BitSize + Default + Into<u32> {
-        match addr {
-            0x0..0x10004 => self.flash.write(addr, value),
-            0x20000000..0x20003ffc => self.ram.write(addr, value),
-            0xe000ed08 => self.registers.vtor.write(value),
-            _ => self.nvic.throw(Exception::BusFault),
+    fn store_multiple(&mut self, addr: u32, registers: u16) {
+        for (index, register) in (0..16).filter(|register| registers & (1 << register) != 0).enumerate() {
+            let value = self.registers.get(register, self.mode);
+
+            self.write::<u32>(addr.wrapping_add(index as u32 * 4) as usize, value);
         }
     }
-}
 
+    fn dispatch(&mut self, inst: &Instruction) {
+        match inst.kind {
+            InstructionKind::Mov { rd, operand, setflags } => {
+                let (result, carry) = self.shifter(operand);
+
+                self.write_result(rd, result, setflags, carry, None);
+            },
+            InstructionKind::Mvn { rd, operand, setflags } => {
+                let (result, carry) = self.shifter(operand);
+
+                self.write_result(rd, !result, setflags, carry, None);
+            },
+            InstructionKind::Add { rd, rn, operand, setflags } => {
+                let (value, _) = self.shifter(operand);
+                let (result, carry, overflow) = add_with_carry(self.registers.get(rn, self.mode), value, false);
+
+                self.write_result(rd, result, setflags, carry, Some(overflow));
+            },
+            InstructionKind::Adc { rd, rn, operand, setflags } => {
+                let (value, _) = self.shifter(operand);
+                let (result, carry, overflow) = add_with_carry(self.registers.get(rn, self.mode), value, self.registers.psr.flag(Flag::C));
+
+                self.write_result(rd, result, setflags, carry, Some(overflow));
+            },
+            InstructionKind::Sub { rd, rn, operand, setflags } => {
+                let (value, _) = self.shifter(operand);
+                let (result, carry, overflow) = add_with_carry(self.registers.get(rn, self.mode), !value, true);
+
+                self.write_result(rd, result, setflags, carry, Some(overflow));
+            },
+            InstructionKind::Sbc { rd, rn, operand, setflags } => {
+                let (value, _) = self.shifter(operand);
+                let (result, carry, overflow) = add_with_carry(self.registers.get(rn, self.mode), !value, self.registers.psr.flag(Flag::C));
+
+                self.write_result(rd, result, setflags, carry, Some(overflow));
+            },
+            InstructionKind::Rsb { rd, rn, operand, setflags } => {
+                let (value, _) = self.shifter(operand);
+                let (result, carry, overflow) = add_with_carry(!self.registers.get(rn, self.mode), value, true);
+
+                self.write_result(rd, result, setflags, carry, Some(overflow));
+            },
+            InstructionKind::And { rd, rn, operand, setflags } => {
+                let (value, carry) = self.shifter(operand);
+
+                self.write_result(rd, self.registers.get(rn, self.mode) & value, setflags, carry, None);
+            },
+            InstructionKind::Orr { rd, rn, operand, setflags } => {
+                let (value, carry) = self.shifter(operand);
+
+                self.write_result(rd, self.registers.get(rn, self.mode) | value, setflags, carry, None);
+            },
+            InstructionKind::Eor { rd, rn, operand, setflags } => {
+                let (value, carry) = self.shifter(operand);
+
+                self.write_result(rd, self.registers.get(rn, self.mode) ^ value, setflags, carry, None);
+            },
+            InstructionKind::Bic { rd, rn, operand, setflags } => {
+                let (value, carry) = self.shifter(operand);
+
+                self.write_result(rd, self.registers.get(rn, self.mode) & !value, setflags, carry, None);
+            },
+            InstructionKind::Orn { rd, rn, operand, setflags } => {
+                let (value, carry) = self.shifter(operand);
+
+                self.write_result(rd, self.registers.get(rn, self.mode) | !value, setflags, carry, None);
+            },
+            InstructionKind::Cmp { rn, operand } => {
+                let (value, _) = self.shifter(operand);
+                let (result, carry, overflow) = add_with_carry(self.registers.get(rn, self.mode), !value, true);
+
+                self.update_flags(result, carry, Some(overflow));
+            },
+            InstructionKind::Cmn { rn, operand } => {
+                let (value, _) = self.shifter(operand);
+                let (result, carry, overflow) = add_with_carry(self.registers.get(rn, self.mode), value, false);
+
+                self.update_flags(result, carry, Some(overflow));
+            },
+            InstructionKind::Tst { rn, operand } => {
+                let (value, carry) = self.shifter(operand);
+
+                self.update_flags(self.registers.get(rn, self.mode) & value, carry, None);
+            },
+            InstructionKind::Teq { rn, operand } => {
+                let (value, carry) = self.shifter(operand);
+
+                self.update_flags(self.registers.get(rn, self.mode) ^ value, carry, None);
+            },
+            InstructionKind::Movt { rd, imm16 } => {
+                self.registers.set(rd, |value| ((imm16 as u32) << 16) | (value & 0xffff), self.mode);
+            },
+            InstructionKind::ShiftReg { kind, rd, rn, rm, setflags } => {
+                let amount = self.registers.get(rm, self.mode) & 0xff;
+                let (result, carry) = shift_c(self.registers.get(rn, self.mode), kind, amount, self.registers.psr.flag(Flag::C));
+
+                self.write_result(rd, result, setflags, carry, None);
+            },
+            InstructionKind::Mul { rd, rn, rm, setflags } => {
+                let result = self.registers.get(rn, self.mode).wrapping_mul(self.registers.get(rm, self.mode));
+
+                self.write_result(rd, result, setflags, self.registers.psr.flag(Flag::C), None);
+            },
+            InstructionKind::Mla { rd, rn, rm, ra, subtract } => {
+                let product = self.registers.get(rn, self.mode).wrapping_mul(self.registers.get(rm, self.mode));
+                let addend = self.registers.get(ra, self.mode);
+
+                self.registers.set(rd, |_| if subtract { addend.wrapping_sub(product) } else { addend.wrapping_add(product) }, self.mode);
+            },
+            InstructionKind::MulLong { rdlo, rdhi, rn, rm, signed, accumulate } => {
+                let (n, m) = (self.registers.get(rn, self.mode), self.registers.get(rm, self.mode));
+
+                let product = match signed {
+                    true => (n as i32 as i64).wrapping_mul(m as i32 as i64) as u64,
+                    false => n as u64 * m as u64,
+                };
+
+                let result = match accumulate {
+                    true => product.wrapping_add(self.long(rdlo, rdhi)),
+                    false => product,
+                };
+
+                self.write_long(rdlo, rdhi, result);
+            },
+            InstructionKind::Umaal { rdlo, rdhi, rn, rm } => {
+                let product = self.registers.get(rn, self.mode) as u64 * self.registers.get(rm, self.mode) as u64;
+                let result = product + self.registers.get(rdlo, self.mode) as u64 + self.registers.get(rdhi, self.mode) as u64;
+
+                self.write_long(rdlo, rdhi, result);
+            },
+            InstructionKind::Div { rd, rn, rm, signed } => {
+                let (n, m) = (self.registers.get(rn, self.mode), self.registers.get(rm, self.mode));
+
+                if m == 0 && self.nvic.scb.ccr.div_0_trp() {
+                    self.fault(Fault::DivideByZero);
+                } else {
+                    let result = match (m, signed) {
+                        (0, _) => 0,
+                        (_, true) => (n as i32).wrapping_div(m as i32) as u32,
+                        (_, false) => n / m,
+                    };
+
+                    self.registers.set(rd, |_| result, self.mode);
+                }
+            },
+            InstructionKind::Smla { rd, rn, rm, ra, n_high, m_high } => {
+                let product = half(self.registers.get(rn, self.mode), n_high) * half(self.registers.get(rm, self.mode), m_high);
+
+                self.write_q(rd, product + self.accumulator(ra));
+            },
+            InstructionKind::Smlaw { rd, rn, rm, ra, m_high } => {
+                let product = self.registers.get(rn, self.mode) as i32 as i64 * half(self.registers.get(rm, self.mode), m_high);
+
+                self.write_q(rd, (product >> 16) + self.accumulator(ra));
+            },
+            InstructionKind::Smlad { rd, rn, rm, ra, exchange, subtract } => {
+                let n = self.registers.get(rn, self.mode);
+                let m = if exchange { self.registers.get(rm, self.mode).rotate_right(16) } else { self.registers.get(rm, self.mode) };
+
+                let (low, high) = (half(n, false) * half(m, false), half(n, true) * half(m, true));
+
+                self.write_q(rd, if subtract { low - high } else { low + high } + self.accumulator(ra));
+            },
+            InstructionKind::Smmla { rd, rn, rm, ra, round, subtract } => {
+                let product = (self.registers.get(rn, self.mode) as i32 as i64).wrapping_mul(self.registers.get(rm, self.mode) as i32 as i64);
+                let addend = (self.accumulator(ra) as u64) << 32;
+
+                let result = match subtract {
+                    true => addend.wrapping_sub(product as u64),
+                    false => addend.wrapping_add(product as u64),
+                };
+
+                let result = if round { result.wrapping_add(0x80000000) } else { result };
+
+                self.registers.set(rd, |_| (result >> 32) as u32, self.mode);
+            },
+            InstructionKind::Smlal { rdlo, rdhi, rn, rm, n_high, m_high } => {
+                let product = half(self.registers.get(rn, self.mode), n_high) * half(self.registers.get(rm, self.mode), m_high);
+
+                self.write_long(rdlo, rdhi, self.long(rdlo, rdhi).wrapping_add(product as u64));
+            },
+            InstructionKind::Smlald { rdlo, rdhi, rn, rm, exchange, subtract } => {
+                let n = self.registers.get(rn, self.mode);
+                let m = if exchange { self.registers.get(rm, self.mode).rotate_right(16) } else { self.registers.get(rm, self.mode) };
+
+                let (low, high) = (half(n, false) * half(m, false), half(n, true) * half(m, true));
+
+                self.write_long(rdlo, rdhi, self.long(rdlo, rdhi).wrapping_add(if subtract { low - high } else { low + high } as u64));
+            },
+            InstructionKind::Parallel { op, prefix, rd, rn, rm } => {
+                let (result, ge) = parallel(op, prefix, self.registers.get(rn, self.mode), self.registers.get(rm, self.mode));
+
+                if let Some(ge) = ge {
+                    self.registers.psr.set_ge(ge);
+                }
+
+                self.registers.set(rd, |_| result, self.mode);
+            },
+            InstructionKind::Qadd { rd, rm, rn, double, subtract } => {
+                let (m, n) = (self.registers.get(rm, self.mode) as i32 as i64, self.registers.get(rn, self.mode) as i32 as i64);
+
+                let (n, doubled) = match double {
+                    true => signed_sat_q(n * 2, 32),
+                    false => (n as u32, false),
+                };
+
+                let n = n as i32 as i64;
+
+                let (result, saturated) = signed_sat_q(if subtract { m - n } else { m + n }, 32);
+
+                if doubled || saturated {
+                    self.registers.psr.set_flag(Flag::Q, true);
+                }
+
+                self.registers.set(rd, |_| result, self.mode);
+            },
+            InstructionKind::Sat { rd, saturate_to, operand, unsigned } => {
+                let value = self.shifter(operand).0 as i32 as i64;
+
+                let (result, saturated) = match unsigned {
+                    true => unsigned_sat_q(value, saturate_to as u32),
+                    false => signed_sat_q(value, saturate_to as u32),
+                };
+
+                if saturated {
+                    self.registers.psr.set_flag(Flag::Q, true);
+                }
+
+                self.registers.set(rd, |_| result, self.mode);
+            },
+            InstructionKind::Sat16 { rd, saturate_to, rn, unsigned } => {
+                let n = self.registers.get(rn, self.mode);
+
+                let ((low, low_saturated), (high, high_saturated)) = match unsigned {
+                    true => (unsigned_sat_q(half(n, false), saturate_to as u32), unsigned_sat_q(half(n, true), saturate_to as u32)),
+                    false => (signed_sat_q(half(n, false), saturate_to as u32), signed_sat_q(half(n, true), saturate_to as u32)),
+                };
+
+                if low_saturated || high_saturated {
+                    self.registers.psr.set_flag(Flag::Q, true);
+                }
+
+                self.registers.set(rd, |_| (high << 16) | (low & 0xffff), self.mode);
+            },
+            InstructionKind::Sel { rd, rn, rm } => {
+                let (n, m, ge) = (self.registers.get(rn, self.mode), self.registers.get(rm, self.mode), self.registers.psr.ge());
+
+                let mask = (0..4).filter(|lane| ge & (1 << lane) != 0).fold(0, |mask, lane| mask | (0xff << (lane * 8)));
+
+                self.registers.set(rd, |_| (n & mask) | (m & !mask), self.mode);
+            },
+            InstructionKind::Usad8 { rd, rn, rm, ra } => {
+                let (n, m) = (self.registers.get(rn, self.mode), self.registers.get(rm, self.mode));
+
+                let sum = (0..4).map(|lane| ((n >> (lane * 8)) as u8).abs_diff((m >> (lane * 8)) as u8) as u32).sum::<u32>();
+                let result = sum.wrapping_add(self.accumulator(ra) as u32);
+
+                self.registers.set(rd, |_| result, self.mode);
+            },
+            InstructionKind::Pkh { rd, rn, operand, tb } => {
+                let (n, shifted) = (self.registers.get(rn, self.mode), self.shifter(operand).0);
+
+                self.registers.set(rd, |_| if tb { (n & 0xffff0000) | (shifted & 0xffff) } else { (shifted & 0xffff0000) | (n & 0xffff) }, self.mode);
+            },
+            InstructionKind::Extend { rd, rn, rm, rotation, width, signed } => {
+                let rotated = self.registers.get(rm, self.mode).rotate_right(rotation as u32);
+
+                let extended = match (width, signed) {
+                    (Width::Byte, true) => rotated as u8 as i8 as u32,
+                    (Width::Byte, false) => rotated as u8 as u32,
+                    (_, true) => rotated as u16 as i16 as u32,
+                    (_, false) => rotated as u16 as u32,
+                };
+
+                let n = rn.map(|rn| self.registers.get(rn, self.mode)).unwrap_or(0);
+
+                self.registers.set(rd, |_| n.wrapping_add(extended), self.mode);
+            },
+            InstructionKind::Bfi { rd, rn, lsb, width } => {
+                let mask = (u32::MAX >> (32 - width as u32)) << lsb;
+                let n = rn.map(|rn| self.registers.get(rn, self.mode) << lsb).unwrap_or(0);
+
+                self.registers.set(rd, |d| (d & !mask) | (n & mask), self.mode);
+            },
+            InstructionKind::Bfx { rd, rn, lsb, width, signed } => {
+                let field = self.registers.get(rn, self.mode) << (32 - width as u32 - lsb as u32);
+
+                let result = match signed {
+                    true => ((field as i32) >> (32 - width as u32)) as u32,
+                    false => field >> (32 - width as u32),
+                };
+
+                self.registers.set(rd, |_| result, self.mode);
+            },
+            InstructionKind::Clz { rd, rm } => {
+                let m = self.registers.get(rm, self.mode);
+
+                self.registers.set(rd, |_| m.leading_zeros(), self.mode);
+            },
+            InstructionKind::Reverse { op, rd, rm } => {
+                let m = self.registers.get(rm, self.mode);
+
+                let result = match op {
+                    ReverseOp::Rev => m.swap_bytes(),
+                    ReverseOp::Rev16 => ((m & 0x00ff00ff) << 8) | ((m >> 8) & 0x00ff00ff),
+                    ReverseOp::Revsh => (m as u16).swap_bytes() as i16 as u32,
+                    ReverseOp::Rbit => m.reverse_bits(),
+                };
+
+                self.registers.set(rd, |_| result, self.mode);
+            },
+            InstructionKind::Extend16 { rd, rn, rm, rotation, signed } => {
+                let (n, rotated) = (rn.map(|rn| self.registers.get(rn, self.mode)).unwrap_or(0), self.registers.get(rm, self.mode).rotate_right(rotation as u32));
+
+                let extend = |byte: u32| if signed { byte as u8 as i8 as u32 } else { byte & 0xff };
+
+                let low = (n as u16).wrapping_add(extend(rotated) as u16) as u32;
+                let high = ((n >> 16) as u16).wrapping_add(extend(rotated >> 16) as u16) as u32;
+
+                self.registers.set(rd, |_| (high << 16) | low, self.mode);
+            },
+            InstructionKind::Vldr { .. } | InstructionKind::Vstr { .. } | InstructionKind::Vldm { .. } | InstructionKind::Vstm { .. }
+                | InstructionKind::VmovCore { .. } | InstructionKind::VmovCorePair { .. } | InstructionKind::VmovImm { .. }
+                | InstructionKind::Vfp { .. } | InstructionKind::VfpUnary { .. } | InstructionKind::Vcmp { .. }
+                | InstructionKind::VcvtInt { .. } | InstructionKind::VcvtFixed { .. } | InstructionKind::VcvtHalf { .. }
+                | InstructionKind::Vmrs { .. } | InstructionKind::Vmsr { .. } => {
+                self.dispatch_fp(&inst.kind);
+            },
+            InstructionKind::Adr { rd, imm32, add } => {
+                let pc = self.registers.get(15, self.mode) & !3;
+
+                self.registers.set(rd, |_| if add { pc.wrapping_add(imm32) } else { pc.wrapping_sub(imm32) }, self.mode);
+            },
+            InstructionKind::B { cond, imm32 } => {
+                if self.registers.psr.passed(cond) {
+                    self.branch_write_pc(self.registers.get(15, self.mode).wrapping_add(imm32 as u32));
+                }
+            },
+            InstructionKind::Bl { imm32 } => {
+                self.registers.set(14, |_| inst.addr.wrapping_add(inst.size) | 1, self.mode);
+
+                self.branch_write_pc(self.registers.get(15, self.mode).wrapping_add(imm32 as u32));
+            },
+            InstructionKind::Bx { rm } => {
+                self.bx_write_pc(self.registers.get(rm, self.mode));
+            },
+            InstructionKind::Blx { rm } => {
+                let target = self.registers.get(rm, self.mode);
+
+                self.registers.set(14, |_| inst.addr.wrapping_add(inst.size) | 1, self.mode);
+
+                self.bx_write_pc(target);
+            },
+            InstructionKind::Cbz { rn, imm32, nonzero } => {
+                if nonzero != (self.registers.get(rn, self.mode) == 0) {
+                    self.branch_write_pc(self.registers.get(15, self.mode).wrapping_add(imm32));
+                }
+            },
+            InstructionKind::Tbb { rn, rm, half } => {
+                let (base, index) = (self.registers.get(rn, self.mode), self.registers.get(rm, self.mode));
+
+                if half && !self.aligned(base.wrapping_add(index << 1), 2, false) {
+                    return;
+                }
+
+                let offset = match half {
+                    true => self.read::<u16>(base.wrapping_add(index << 1) as usize) as u32,
+                    false => self.read::<u8>(base.wrapping_add(index) as usize) as u32,
+                };
+
+                if self.bus_error.is_some() {
+                    return;
+                }
+
+                self.branch_write_pc(self.registers.get(15, self.mode).wrapping_add(offset * 2));
+            },
+            InstructionKind::Ldr { rt, address, width, signed } => {
+                let (addr, offset_addr) = self.address(address);
+
+                if !self.aligned(addr, width.size(), false) {
+                    return;
+                }
+
+                let data = self.load(addr, width, signed);
+
+                if self.bus_error.is_some() {
+                    return;
+                }
+
+                if address.wback {
+                    self.registers.set(address.rn, |_| offset_addr, self.mode);
+                }
+
+                match rt {
+                    15 => self.bx_write_pc(data),
+                    _ => self.registers.set(rt, |_| data, self.mode),
+                }
+            },
+            InstructionKind::Ldrex { rt, address, width } => {
+                let (addr, _) = self.address(address);
+
+                if !self.aligned(addr, width.size(), true) {
+                    return;
+                }
+
+                let data = self.load(addr, width, false);
+
+                if self.bus_error.is_some() {
+                    return;
+                }
+
+                self.exclusive = Some(addr);
+
+                self.registers.set(rt, |_| data, self.mode);
+            },
+            InstructionKind::Strex { rd, rt, address, width } => {
+                let (addr, _) = self.address(address);
+
+                if !self.aligned(addr, width.size(), true) {
+                    return;
+                }
+
+                let status = match self.exclusive.take() {
+                    Some(tagged) if tagged == addr => {
+                        self.store(addr, width, self.registers.get(rt, self.mode));
+
+                        0
+                    },
+                    _ => 1,
+                };
+
+                self.registers.set(rd, |_| status, self.mode);
+            },
+            InstructionKind::Clrex => self.exclusive = None,
+            InstructionKind::Str { rt, address, width } => {
+                let (addr, offset_addr) = self.address(address);
+
+                if !self.aligned(addr, width.size(), false) {
+                    return;
+                }
+
+                self.store(addr, width, self.registers.get(rt, self.mode));
+
+                if self.bus_error.is_some() {
+                    return;
+                }
+
+                if address.wback {
+                    self.registers.set(address.rn, |_| offset_addr, self.mode);
+                }
+            },
+            InstructionKind::Ldrd { rt, rt2, address } => {
+                let (addr, offset_addr) = self.address(address);
+
+                if !self.aligned(addr, 4, true) {
+                    return;
+                }
+
+                let (low, high) = (self.read::<u32>(addr as usize), self.read::<u32>(addr.wrapping_add(4) as usize));
+
+                if self.bus_error.is_some() {
+                    return;
+                }
+
+                self.registers.set(rt, |_| low, self.mode);
+                self.registers.set(rt2, |_| high, self.mode);
+
+                if address.wback {
+                    self.registers.set(address.rn, |_| offset_addr, self.mode);
+                }
+            },
+            InstructionKind::Strd { rt, rt2, address } => {
+                let (addr, offset_addr) = self.address(address);
+
+                if !self.aligned(addr, 4, true) {
+                    return;
+                }
+
+                self.write::<u32>(addr as usize, self.registers.get(rt, self.mode));
+                self.write::<u32>(addr.wrapping_add(4) as usize, self.registers.get(rt2, self.mode));
+
+                if self.bus_error.is_some() {
+                    return;
+                }
+
+                if address.wback {
+                    self.registers.set(address.rn, |_| offset_addr, self.mode);
+                }
+            },
+            InstructionKind::Pld { .. } => {},
+            InstructionKind::Ldm { rn, registers, wback } => {
+                let addr = self.registers.get(rn, self.mode);
+
+                if !self.aligned(addr, 4, true) {
+                    return;
+                }
+
+                let Some(values) = self.load_multiple(addr, registers) else {
+                    return;
+                };
+
+                if wback && registers & (1 << rn) == 0 {
+                    self.registers.set(rn, |_| addr.wrapping_add(4 * registers.count_ones()), self.mode);
+                }
+
+                self.commit_multiple(values);
+            },
+            InstructionKind::Ldmdb { rn, registers, wback } => {
+                let addr = self.registers.get(rn, self.mode).wrapping_sub(4 * registers.count_ones());
+
+                if !self.aligned(addr, 4, true) {
+                    return;
+                }
+
+                let Some(values) = self.load_multiple(addr, registers) else {
+                    return;
+                };
+
+                if wback && registers & (1 << rn) == 0 {
+                    self.registers.set(rn, |_| addr, self.mode);
+                }
+
+                self.commit_multiple(values);
+            },
+            InstructionKind::Stmdb { rn, registers, wback } => {
+                let addr = self.registers.get(rn, self.mode).wrapping_sub(4 * registers.count_ones());
+
+                if !self.aligned(addr, 4, true) {
+                    return;
+                }
+
+                self.store_multiple(addr, registers);
+
+                if self.bus_error.is_some() {
+                    return;
+                }
+
+                if wback {
+                    self.registers.set(rn, |_| addr, self.mode);
+                }
+            },
+            InstructionKind::Stm { rn, registers, wback } => {
+                let addr = self.registers.get(rn, self.mode);
+
+                if !self.aligned(addr, 4, true) {
+                    return;
+                }
+
+                self.store_multiple(addr, registers);
+
+                if self.bus_error.is_some() {
+                    return;
+                }
+
+                if wback {
+                    self.registers.set(rn, |_| addr.wrapping_add(4 * registers.count_ones()), self.mode);
+                }
+            },
+            InstructionKind::Push { registers } => {
+                let addr = self.registers.get(13, self.mode).wrapping_sub(4 * registers.count_ones());
+
+                if !self.aligned(addr, 4, true) {
+                    return;
+                }
+
+                self.store_multiple(addr, registers);
+
+                if self.bus_error.is_some() {
+                    return;
+                }
+
+                self.registers.set(13, |_| addr, self.mode);
+            },
+            InstructionKind::Pop { registers } => {
+                let addr = self.registers.get(13, self.mode);
+
+                if !self.aligned(addr, 4, true) {
+                    return;
+                }
+
+                let Some(values) = self.load_multiple(addr, registers) else {
+                    return;
+                };
+
+                self.registers.set(13, |_| addr.wrapping_add(4 * registers.count_ones()), self.mode);
+
+                self.commit_multiple(values);
+            },
+            InstructionKind::It { firstcond, mask } => {
+                self.registers.psr.set_it_state(((firstcond as u8) << 4) | mask);
+            },
+            InstructionKind::Svc { .. } => {
+                self.raise(Exception::SVCall);
+            },
+            InstructionKind::Bkpt { .. } => {
+                // halting debug and the debug monitor are never enabled, so bkpt escalates
+                self.nvic.scb.dfsr |= 1 << 1;
+                self.nvic.scb.hfsr |= 1 << 31;
+
+                self.raise(Exception::HardFault);
+            },
+            InstructionKind::Nop | InstructionKind::Yield => {},
+            InstructionKind::Wfi => self.sleep = Some(Sleep::Interrupt),
+            // an event that arrived earlier is consumed instead of sleeping
+            InstructionKind::Wfe => {
+                if !std::mem::take(&mut self.nvic.event) {
+                    self.sleep = Some(Sleep::Event);
+                }
+            },
+            InstructionKind::Sev => self.nvic.event = true,
+            InstructionKind::Mrs { rd, sysm } => {
+                let value = self.read_special(sysm);
+
+                self.registers.set(rd, |_| value, self.mode);
+            },
+            InstructionKind::Msr { rn, sysm, mask } => self.write_special(sysm, mask, self.registers.get(rn, self.mode)),
+            InstructionKind::Cps { enable, primask, faultmask } => {
+                if self.privileged() {
+                    if primask {
+                        self.registers.primask = !enable;
+                    }
+
+                    if faultmask && self.faultmask_allowed() {
+                        self.registers.faultmask = !enable;
+                    }
+                }
+            },
+            // memory accesses complete in order and there is no prefetch to flush
+            InstructionKind::Dsb { .. } | InstructionKind::Dmb { .. } | InstructionKind::Isb { .. } => {},
+            InstructionKind::Undefined => self.fault(Fault::Undefined),
+        }
+    }
+
+    fn execute(&mut self) -> Option<Stop> {
+        self.bus_error = None;
+        self.branch = false;
+        self.faulted = false;
+
+        // page 47 @ ARMv7M Reference Manual
+        if !self.registers.psr.get(24) {
+            self.fault(Fault::InvalidState);
+            self.cycles += 1;
+
+            return None;
+        }
+
+        let inst = self.fetch();
+
+        if self.bus_error.take().is_some() {
+            self.fault(Fault::InstructionBus);
+            self.cycles += 1;
+
+            return None;
+        }
+
+        if matches!(inst.kind, InstructionKind::Undefined) && self.undefined == UndefinedPolicy::Stop {
+            let opcode = match inst.size {
+                4 => ((self.read::<u16>(inst.addr as usize) as u32) << 16) | self.read::<u16>(inst.addr as usize + 2) as u32,
+                _ => self.read::<u16>(inst.addr as usize) as u32,
+            };
+
+            return Some(Stop::Undefined { addr: inst.addr, opcode });
+        }
+
+        let (passed, it) = (self.registers.psr.passed(inst.condition), self.registers.psr.it_state());
+
+        // advancing before dispatch lets it and exception returns overwrite the state
+        self.registers.psr.it_advance();
+
+        if passed {
+            self.dispatch(&inst);
+        }
+
+        if let Some(addr) = self.bus_error.take() {
+            self.fault(Fault::PreciseData { addr });
+        }
+
+        // a faulting instruction is stacked with the it state it started with so it can be retried
+        if self.faulted {
+            self.registers.psr.set_it_state(it);
+        }
+
+        // instructions that fail their condition still occupy the pipeline for a cycle
+        self.cycles += if passed { inst.kind.cycles() } else { 1 };
+
+        if !self.branch {
+            self.registers.set(15, |pc| pc.wrapping_add(inst.size), self.mode);
+        } else {
+            self.cycles += 2;
+        }
+
+        None
+    }
+
+    fn handle_exception(&mut self) {
+        if let Some(exception) = self.nvic.poll(self.execution_priority()) {
+            match (exception, self.sleep.take()) {
+                (Exception::Reset, _) => self.reset(),
+                (_, Some(Sleep::Exit(exc_return))) => {
+                    self.registers.set(14, |_| 0xf0000000 | exc_return, self.mode);
+
+                    self.exception_entry(exception);
+
+                    self.cycles += TAIL_CHAIN_CYCLES;
+                },
+                _ => {
+                    self.bus_error = None;
+                    self.lockup = false;
+
+                    self.push_stack();
+
+                    let stacking = self.bus_error.take().is_some();
+
+                    self.cycles += ENTRY_CYCLES;
+
+                    self.tick(ENTRY_CYCLES);
+
+                    // a more urgent exception arriving while stacking takes over the frame, the original is tail-chained later
+                    let exception = match self.nvic.poll(self.nvic.scb.group(self.nvic.priority(exception))) {
+                        Some(late) => {
+                            self.nvic.repend(exception);
+
+                            late
+                        },
+                        None => exception,
+                    };
+
+                    self.exception_entry(exception);
+
+                    if stacking {
+                        self.record_fault(Fault::Stacking);
+                    }
+                },
+            }
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        if self.systick.tick(cycles) {
+            self.nvic.throw(Exception::SysTick);
+        }
+
+        // a level held high by a device pends its interrupt again once the handler is no longer active
+        for irq in self.bus.tick(cycles) {
+            if !self.nvic.is_active(16 + irq) {
+                self.nvic.throw(Exception::Interrupt { offset: 16 + irq });
+            }
+        }
+    }
+
+    pub fn step(&mut self) -> Option<Stop> {
+        let cycles = self.cycles;
+
+        // a locked up processor stops fetching but can still be reset or preempted by nmi
+        if self.lockup || self.sleep.is_some() {
+            self.cycles += 1;
+        } else if let Some(stop) = self.execute() {
+            return Some(stop);
+        }
+
+        self.tick(self.cycles - cycles);
+
+        // wfe also wakes on events, exceptions that get taken wake every kind of sleep
+        if self.sleep == Some(Sleep::Event) && std::mem::take(&mut self.nvic.event) {
+            self.sleep = None;
+        }
+
+        self.handle_exception();
+
+        self.lockup.then_some(Stop::Lockup)
+    }
+}
+
+// each word in the bit-band alias regions maps to one bit of the sram and peripheral regions below them,
+// the region is accessed with the size of the alias access
+fn bitband(addr: usize, size: usize) -> Option<(usize, u32)> {
+    match addr {
+        0x22000000..0x24000000 | 0x42000000..0x44000000 => {
+            let byte = (addr & 0xfe000000) - 0x02000000 + ((addr & 0x01ffffff) >> 5);
+
+            Some((byte & !(size - 1), ((byte & (size - 1)) * 8 + ((addr >> 2) & 7)) as u32))
+        },
+        _ => None,
+    }
+}
+
+impl Processor {
+    fn read_sized(&mut self, addr: usize, size: usize) -> u32 {
+        match size {
+            1 => self.read::<u8>(addr) as u32,
+            2 => self.read::<u16>(addr) as u32,
+            _ => self.read::<u32>(addr),
+        }
+    }
+
+    fn write_sized(&mut self, addr: usize, size: usize, value: u32) {
+        match size {
+            1 => self.write::<u8>(addr, value as u8),
+            2 => self.write::<u16>(addr, value as u16),
+            _ => self.write::<u32>(addr, value),
+        }
+    }
+}
+
+impl DataBus for Processor {
+    fn read<T>(&mut self, addr: usize) -> T where T: BitSize + Default {
+        let physical = self.translate(addr);
+
+        // mapped memory wins over the bit-band alias regions, machine descriptions are not allowed to overlap them
+        if let Some(memory) = self.memory.iter_mut().find(|memory| memory.contains(physical, std::mem::size_of::<T>())) {
+            return match memory.permissions.read {
+                true => memory.read(physical),
+                false => { self.bus_error = Some(addr as u32); T::default() },
+            };
+        }
+
+        if let Some((target, bit)) = bitband(addr, std::mem::size_of::<T>()) {
+            let value = self.read_sized(target, std::mem::size_of::<T>());
+
+            return T::from(&((value >> bit) & 1).to_bytes());
+        }
+
+        match addr {
+            0xe000e100..0xe000e5f0 => T::from(&(self.nvic.read_register((addr & !3) - 0xe000e100) >> ((addr & 3) * 8)).to_bytes()),
+            0xe000e010..0xe000e020 => T::from(&(self.systick.read_register((addr & !3) - 0xe000e010) >> ((addr & 3) * 8)).to_bytes()),
+            0xe000ed08 => self.registers.vtor.read(),
+            0xe000ed04..0xe000ed40 => T::from(&(self.nvic.read_system_register((addr & !3) - 0xe000ed00) >> ((addr & 3) * 8)).to_bytes()),
+            0xe000ed88 => self.registers.cpacr.read(),
+            0xe000ef34 => self.registers.fpccr.read(),
+            0xe000ef38 => T::from(&self.registers.fpcar.to_bytes()),
+            0xe000ef3c => T::from(&self.registers.fpdscr.to_bytes()),
+            _ => match self.bus.read(addr, std::mem::size_of::<T>()) {
+                Some(value) => T::from(&value.to_bytes()),
+                None => { self.bus_error = Some(addr as u32); T::default() },
+            },
+        }
+    }
+
+    fn write<T>(&mut self, addr: usize, value: T) where u32: From<T>, T: BitSize + Default + Into<u32> {
+        let physical = self.translate(addr);
+
+        if let Some(memory) = self.memory.iter_mut().find(|memory| memory.contains(physical, std::mem::size_of::<T>())) {
+            match memory.permissions.write {
+                true => memory.write(physical, value),
+                false => self.bus_error = Some(addr as u32),
+            }
+
+            return;
+        }
+
+        // the read-modify-write cannot be interrupted, so the bit is updated atomically
+        if let Some((target, bit)) = bitband(addr, std::mem::size_of::<T>()) {
+            let current = self.read_sized(target, std::mem::size_of::<T>());
+
+            self.write_sized(target, std::mem::size_of::<T>(), (current & !(1 << bit)) | ((Into::<u32>::into(value) & 1) << bit));
+
+            return;
+        }
+
+        match addr {
+            0xe000e100..0xe000e5f0 => {
+                let (shift, bits) = ((addr & 3) * 8, std::mem::size_of::<T>() * 8);
+
+                self.nvic.write_register((addr & !3) - 0xe000e100, Into::<u32>::into(value) << shift, (u32::MAX >> (32 - bits)) << shift);
+            },
+            0xe000e010..0xe000e020 => {
+                let (shift, bits) = ((addr & 3) * 8, std::mem::size_of::<T>() * 8);
+
+                self.systick.write_register((addr & !3) - 0xe000e010, Into::<u32>::into(value) << shift, (u32::MAX >> (32 - bits)) << shift);
+            },
+            0xe000ed08 => self.registers.vtor.write(value),
+            0xe000ed04..0xe000ed40 => {
+                let (shift, bits) = ((addr & 3) * 8, std::mem::size_of::<T>() * 8);
+
+                if self.nvic.write_system_register((addr & !3) - 0xe000ed00, Into::<u32>::into(value) << shift, (u32::MAX >> (32 - bits)) << shift) {
+                    self.nvic.throw(Exception::Reset);
+                }
+            },
+            0xe000ed88 => self.registers.cpacr.write(value),
+            0xe000ef34 => self.registers.fpccr.write(value),
+            0xe000ef38 => self.registers.fpcar = Into::<u32>::into(value) & !0x7,
+            0xe000ef3c => self.registers.fpdscr = Into::<u32>::into(value) & 0x07c00000,
+            _ => {
+                if !self.bus.write(addr, std::mem::size_of::<T>(), value.into()) {
+                    self.bus_error = Some(addr as u32);
+                }
+            },
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processor(code: &[u16]) -> Processor {
+        let mut processor = Processor::with_machine(&Machine::flat());
+
+        for (offset, halfword) in code.iter().enumerate() {
+            processor.write::<u16>(0x100 + offset * 2, *halfword);
+        }
+
+        processor.registers.set(15, |_| 0x100, processor.mode);
+        processor.registers.set(13, |_| 0x20001000, processor.mode);
+        processor.registers.psr.set(24);
+
+        processor
+    }
+
+    fn run(processor: &mut Processor, steps: usize) {
+        for _ in 0..steps {
+            processor.step();
+        }
+    }
+
+    fn reg(processor: &Processor, register: u8) -> u32 {
+        processor.registers.get(register, processor.mode)
+    }
+
+    fn set(processor: &mut Processor, register: u8, value: u32) {
+        processor.registers.set(register, |_| value, processor.mode);
+    }
+
+    fn nzcv(processor: &Processor) -> (bool, bool, bool, bool) {
+        let psr = &processor.registers.psr;
+
+        (psr.flag(Flag::N), psr.flag(Flag::Z), psr.flag(Flag::C), psr.flag(Flag::V))
+    }
+
+    #[test]
+    fn shift_immediate() {
+        // lsls r0, r1, #2; lsrs r2, r1, #1; asrs r3, r4, #4; movs r5, r1
+        let mut processor = processor(&[0x0088, 0x084a, 0x1123, 0x000d]);
+
+        set(&mut processor, 1, 0x40000001);
+        set(&mut processor, 4, 0x80000000);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x00000004);
+        assert_eq!(nzcv(&processor), (false, false, true, false));
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 2), 0x20000000);
+        assert!(nzcv(&processor).2);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 3), 0xf8000000);
+        assert_eq!(nzcv(&processor), (true, false, false, false));
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 5), 0x40000001);
+        assert_eq!(reg(&processor, 15), 0x108 + 4);
+    }
+
+    #[test]
+    fn add_sub_mov_compare() {
+        // movs r0, #42; adds r1, r0, #3; subs r2, r1, #5; adds r3, r0, r1; subs r4, r0, r1; cmp r0, #42; adds r0, #1; subs r0, #2
+        let mut processor = processor(&[0x202a, 0x1cc1, 0x1f4a, 0x1843, 0x1a44, 0x282a, 0x3001, 0x3802]);
+
+        run(&mut processor, 5);
+        assert_eq!(reg(&processor, 0), 42);
+        assert_eq!(reg(&processor, 1), 45);
+        assert_eq!(reg(&processor, 2), 40);
+        assert_eq!(reg(&processor, 3), 87);
+        assert_eq!(reg(&processor, 4), (-3i32) as u32);
+        assert_eq!(nzcv(&processor), (true, false, false, false));
+
+        run(&mut processor, 1);
+        assert_eq!(nzcv(&processor), (false, true, true, false));
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 0), 41);
+    }
+
+    #[test]
+    fn data_processing() {
+        // ands r0, r1; eors r0, r1; lsls r0, r2; lsrs r0, r2; asrs r0, r2; adcs r0, r1; sbcs r0, r1; rors r0, r2
+        let mut processor = processor(&[0x4008, 0x4048, 0x4090, 0x40d0, 0x4110, 0x4148, 0x4188, 0x41d0]);
+
+        set(&mut processor, 0, 0xff00ff00);
+        set(&mut processor, 1, 0x0ff00ff0);
+        set(&mut processor, 2, 4);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x0f000f00);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x00f000f0);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x0f000f00);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x00f000f0);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x000f000f);
+
+        processor.registers.psr.set_flag(Flag::C, true);
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x000f000f + 0x0ff00ff0 + 1);
+
+        processor.registers.psr.set_flag(Flag::C, false);
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x000f000f);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0xf000f000);
+        assert!(nzcv(&processor).2);
+    }
+
+    #[test]
+    fn data_processing_compare() {
+        // tst r0, r1; rsbs r2, r1; cmp r0, r1; cmn r0, r1; orrs r0, r1; muls r3, r1; bics r0, r1; mvns r4, r1
+        let mut processor = processor(&[0x4208, 0x424a, 0x4288, 0x42c8, 0x4308, 0x434b, 0x4388, 0x43cc]);
+
+        set(&mut processor, 0, 0xf0);
+        set(&mut processor, 1, 0x0f);
+        set(&mut processor, 3, 3);
+
+        run(&mut processor, 1);
+        assert!(nzcv(&processor).1);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 2), (-15i32) as u32);
+
+        run(&mut processor, 1);
+        assert_eq!(nzcv(&processor), (false, false, true, false));
+
+        run(&mut processor, 1);
+        assert_eq!(nzcv(&processor), (false, false, false, false));
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0xff);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 3), 45);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0xf0);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 4), !0x0f);
+        assert!(nzcv(&processor).0);
+    }
+
+    #[test]
+    fn special_data() {
+        // add r8, r1; cmp r8, r1; mov r9, r8; mov r0, pc
+        let mut processor = processor(&[0x4488, 0x4588, 0x46c1, 0x4678]);
+
+        set(&mut processor, 8, 5);
+        set(&mut processor, 1, 10);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 8), 15);
+
+        run(&mut processor, 1);
+        assert_eq!(nzcv(&processor), (false, false, true, false));
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 9), 15);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x106 + 4);
+    }
+
+    #[test]
+    fn branch_exchange() {
+        // blx r3; nop; ...; bx lr at 0x120
+        let mut code = vec![0xbf00; 0x12];
+
+        code[0] = 0x4798;
+        code[0x10] = 0x4770;
+
+        let mut processor = processor(&code);
+
+        set(&mut processor, 3, 0x121);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x120);
+        assert_eq!(reg(&processor, 14), 0x103);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x102);
+    }
+
+    #[test]
+    fn load_store_register() {
+        // str r0, [r1, r2]; strh r0, [r1, r2]; strb r0, [r1, r2]; ldrsb r3, [r1, r2]; ldr r4, [r1, r2]; ldrh r5, [r1, r2]; ldrb r6, [r1, r2]; ldrsh r7, [r1, r2]
+        let mut processor = processor(&[0x5088, 0x5288, 0x5488, 0x568b, 0x588c, 0x5a8d, 0x5c8e, 0x5e8f]);
+
+        set(&mut processor, 0, 0x12345678);
+        set(&mut processor, 1, 0x20000000);
+        set(&mut processor, 2, 0x10);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.read::<u32>(0x20000010), 0x12345678);
+
+        set(&mut processor, 0, 0xaaaa8899);
+        run(&mut processor, 1);
+        assert_eq!(processor.read::<u32>(0x20000010), 0x12348899);
+
+        set(&mut processor, 0, 0x000000f0);
+        run(&mut processor, 1);
+        assert_eq!(processor.read::<u32>(0x20000010), 0x123488f0);
+
+        run(&mut processor, 5);
+        assert_eq!(reg(&processor, 3), 0xfffffff0);
+        assert_eq!(reg(&processor, 4), 0x123488f0);
+        assert_eq!(reg(&processor, 5), 0x88f0);
+        assert_eq!(reg(&processor, 6), 0xf0);
+        assert_eq!(reg(&processor, 7), 0xffff88f0);
+    }
+
+    #[test]
+    fn load_store_immediate() {
+        // str r0, [r1, #4]; ldr r2, [r1, #4]; strb r0, [r1, #4]; ldrb r3, [r1, #4]; strh r0, [r1, #4]; ldrh r4, [r1, #4]; str r0, [sp, #4]; ldr r5, [sp, #4]
+        let mut processor = processor(&[0x6048, 0x684a, 0x7108, 0x790b, 0x8088, 0x888c, 0x9001, 0x9d01]);
+
+        set(&mut processor, 0, 0xcafebabe);
+        set(&mut processor, 1, 0x20000000);
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 2), 0xcafebabe);
+
+        set(&mut processor, 0, 0x11);
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 3), 0x11);
+
+        set(&mut processor, 0, 0x2233);
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 4), 0x2233);
+        assert_eq!(processor.read::<u32>(0x20000004), 0xcafe2233);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.read::<u32>(0x20001004), 0x2233);
+        assert_eq!(reg(&processor, 5), 0x2233);
+    }
+
+    #[test]
+    fn load_literal_and_adr() {
+        // ldr r0, [pc, #4]; adr r1, #4; add r2, sp, #8; nop; .word 0xdeadbeef
+        let mut processor = processor(&[0x4801, 0xa101, 0xaa02, 0xbf00, 0xbeef, 0xdead]);
+
+        run(&mut processor, 3);
+        assert_eq!(reg(&processor, 0), 0xdeadbeef);
+        assert_eq!(reg(&processor, 1), 0x108);
+        assert_eq!(reg(&processor, 2), 0x20001008);
+    }
+
+    #[test]
+    fn adjust_stack_pointer() {
+        // sub sp, #8; add sp, #4
+        let mut processor = processor(&[0xb082, 0xb001]);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 13), 0x20000ff8);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 13), 0x20000ffc);
+    }
+
+    #[test]
+    fn push_pop() {
+        // push {r0, r1, lr}; pop {r2, r3, pc}
+        let mut code = vec![0xbf00; 0x12];
+
+        code[0] = 0xb503;
+        code[1] = 0xbd0c;
+
+        let mut processor = processor(&code);
+
+        set(&mut processor, 0, 1);
+        set(&mut processor, 1, 2);
+        set(&mut processor, 14, 0x121);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 13), 0x20000ff4);
+        assert_eq!(processor.read::<u32>(0x20000ff4), 1);
+        assert_eq!(processor.read::<u32>(0x20000ffc), 0x121);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 13), 0x20001000);
+        assert_eq!((reg(&processor, 2), reg(&processor, 3)), (1, 2));
+        assert_eq!(processor.registers.pc(), 0x120);
+    }
+
+    #[test]
+    fn load_store_multiple() {
+        // stm r0!, {r1, r2}; ldm r3!, {r4, r5}; ldm r6, {r6, r7}
+        let mut processor = processor(&[0xc006, 0xcb30, 0xcec0]);
+
+        set(&mut processor, 0, 0x20000000);
+        set(&mut processor, 1, 11);
+        set(&mut processor, 2, 22);
+        set(&mut processor, 3, 0x20000000);
+        set(&mut processor, 6, 0x20000000);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x20000008);
+
+        run(&mut processor, 1);
+        assert_eq!((reg(&processor, 4), reg(&processor, 5)), (11, 22));
+        assert_eq!(reg(&processor, 3), 0x20000008);
+
+        run(&mut processor, 1);
+        assert_eq!((reg(&processor, 6), reg(&processor, 7)), (11, 22));
+    }
+
+    #[test]
+    fn compare_and_branch() {
+        // cbz r0, #4; nop; nop; nop; cbnz r0, #0
+        let mut processor = processor(&[0xb110, 0xbf00, 0xbf00, 0xbf00, 0xb900]);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x108);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x10a);
+    }
+
+    #[test]
+    fn conditional_branch() {
+        // cmp r0, #1; beq #-6; bne #-8
+        let mut processor = processor(&[0x2801, 0xd0fd, 0xd1fc]);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.registers.pc(), 0x104);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x100);
+    }
+
+    #[test]
+    fn unconditional_branch() {
+        // b #4; nop; nop; b #-4
+        let mut processor = processor(&[0xe001, 0xbf00, 0xbf00, 0xe7fe]);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x106);
+
+        run(&mut processor, 3);
+        assert_eq!(processor.registers.pc(), 0x106);
+    }
+
+    #[test]
+    fn supervisor_call() {
+        // svc #0 with the handler at 0x180
+        let mut processor = processor(&[0xdf00]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::SVCall) * 4, 0x181);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.mode, Mode::Handle);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(reg(&processor, 14), 0xfffffff9);
+        assert_eq!(processor.read::<u32>(reg(&processor, 13) as usize + 0x18), 0x102);
+    }
+
+    #[test]
+    fn breakpoint_and_hints() {
+        // nop; yield; sev; wfe; bkpt #0
+        let mut processor = processor(&[0xbf00, 0xbf10, 0xbf40, 0xbf20, 0xbe00]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::HardFault) * 4, 0x181);
+
+        // the event from sev is consumed by wfe instead of sleeping
+        run(&mut processor, 4);
+        assert_eq!(processor.registers.pc(), 0x108);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+
+        // bkpt #0 as the hardfault handler cannot preempt itself
+        processor.write::<u16>(0x180, 0xbe00);
+
+        assert_eq!(processor.step(), Some(Stop::Lockup));
+        assert_eq!(processor.registers.pc(), 0xfffffffe);
+    }
+
+    #[test]
+    fn wait_for_interrupt() {
+        // wfi; nop with bx lr as the pendsv handler at 0x180
+        let mut processor = processor(&[0xbf30, 0xbf00]);
+
+        processor.write::<u16>(0x180, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::PendSV) * 4, 0x181);
+
+        run(&mut processor, 3);
+        assert_eq!(processor.registers.pc(), 0x102);
+        assert_eq!(processor.cycles, 1 + 2);
+
+        processor.write::<u32>(0xe000ed04, 1 << 28);
+
+        run(&mut processor, 3);
+        assert_eq!(processor.registers.pc(), 0x104);
+    }
+
+    #[test]
+    fn wait_for_event() {
+        // wfe; nop with irq 0 and irq 1 disabled
+        let mut processor = processor(&[0xbf20, 0xbf00]);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.registers.pc(), 0x102);
+
+        // without sevonpend a pending interrupt that cannot be taken is not an event
+        processor.write::<u32>(0xe000e200, 1 << 0);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.registers.pc(), 0x102);
+
+        processor.write::<u32>(0xe000ed10, 1 << 4);
+        processor.write::<u32>(0xe000e200, 1 << 1);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.registers.pc(), 0x104);
+    }
+
+    #[test]
+    fn sleep_on_exit() {
+        // nop; nop with bx lr as the pendsv handler at 0x180
+        let mut processor = processor(&[0xbf00, 0xbf00]);
+
+        processor.write::<u16>(0x180, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::PendSV) * 4, 0x181);
+        processor.write::<u32>(0xe000ed10, 1 << 1);
+        processor.write::<u32>(0xe000ed04, 1 << 28);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+
+        // the return sleeps with the thread frame still stacked
+        run(&mut processor, 3);
+        assert_eq!(processor.mode, Mode::Handle);
+        assert_eq!(processor.registers.sp.msp, 0x20000fe0);
+
+        processor.write::<u32>(0xe000ed04, 1 << 28);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.registers.sp.msp, 0x20000fe0);
+        assert_eq!(reg(&processor, 14), 0xfffffff9);
+
+        processor.write::<u32>(0xe000ed10, 0);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.mode, Mode::Thread);
+        assert_eq!(processor.registers.pc(), 0x104);
+        assert_eq!(processor.registers.sp.msp, 0x20001000);
+    }
+
+    #[test]
+    fn wide_data_processing() {
+        // movw r0, #0x5678; movt r0, #0x1234; add.w r1, r0, r0, lsl #1; orn r2, r3, r4; teq.w r0, #1; lsl.w r5, r0, r6
+        let mut processor = processor(&[0xf245, 0x6078, 0xf2c1, 0x2034, 0xeb00, 0x0140, 0xea63, 0x0204, 0xf090, 0x0f01, 0xfa00, 0xf506]);
+
+        set(&mut processor, 0, 0xffffffff);
+        set(&mut processor, 4, 0xfffffff0);
+        set(&mut processor, 6, 4);
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 0), 0x12345678);
+        assert_eq!(processor.registers.pc(), 0x108);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 1), 0x12345678 * 3);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 2), 0x0000000f);
+
+        run(&mut processor, 1);
+        assert_eq!(nzcv(&processor), (false, false, false, false));
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 5), 0x23456780);
+    }
+
+    #[test]
+    fn wide_flag_setting() {
+        // movs.w r0, #0x80000000; subs.w r1, r0, #1; cmp.w r0, r1, lsr #4
+        let mut processor = processor(&[0xf05f, 0x4000, 0xf1b0, 0x0101, 0xebb0, 0x1f11]);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x80000000);
+        assert_eq!(nzcv(&processor), (true, false, true, false));
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 1), 0x7fffffff);
+        assert_eq!(nzcv(&processor), (false, false, true, true));
+
+        run(&mut processor, 1);
+        assert_eq!(nzcv(&processor), (false, false, true, true));
+    }
+
+    #[test]
+    fn branch_with_link() {
+        // bl #8 lands on the bx lr
+        let mut processor = processor(&[0xf000, 0xf804, 0xbf00, 0xbf00, 0xbf00, 0xbf00, 0x4770]);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x10c);
+        assert_eq!(reg(&processor, 14), 0x105);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x104);
+    }
+
+    #[test]
+    fn wide_conditional_branch() {
+        // cmp r0, #0; beq.w #256; bne.w #-8
+        let mut processor = processor(&[0x2800, 0xf000, 0x8080, 0xf47f, 0xaffc]);
+
+        set(&mut processor, 0, 1);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.registers.pc(), 0x106);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x102);
+    }
+
+    #[test]
+    fn wide_load_store() {
+        // str.w r0, [r1, #4]!; ldr r2, [r1], #-4; ldrsh.w r3, [r1, #4]; ldr.w r4, [r1, r5, lsl #2]
+        let mut processor = processor(&[0xf841, 0x0f04, 0xf851, 0x2904, 0xf9b1, 0x3004, 0xf851, 0x4025]);
+
+        set(&mut processor, 0, 0x0000fffe);
+        set(&mut processor, 1, 0x20000000);
+        set(&mut processor, 5, 1);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 1), 0x20000004);
+        assert_eq!(processor.read::<u32>(0x20000004), 0xfffe);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 2), 0xfffe);
+        assert_eq!(reg(&processor, 1), 0x20000000);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 3), (-2i32) as u32);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 4), 0xfffe);
+    }
+
+    #[test]
+    fn load_store_dual() {
+        // strd r0, r1, [r2, #-8]!; ldrd r3, r4, [r2]
+        let mut processor = processor(&[0xe962, 0x0102, 0xe9d2, 0x3400]);
+
+        set(&mut processor, 0, 0xaaaa);
+        set(&mut processor, 1, 0xbbbb);
+        set(&mut processor, 2, 0x20000010);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 2), 0x20000008);
+
+        run(&mut processor, 1);
+        assert_eq!((reg(&processor, 3), reg(&processor, 4)), (0xaaaa, 0xbbbb));
+    }
+
+    #[test]
+    fn wide_load_store_multiple() {
+        // push.w {r4, r8, lr}; stmdb r0!, {r1, r2}; ldmdb r3, {r5, r6}; pop.w {r4, r8, pc}
+        let mut code = vec![0xbf00; 0x12];
+
+        code[0..8].copy_from_slice(&[0xe92d, 0x4110, 0xe920, 0x0006, 0xe913, 0x0060, 0xe8bd, 0x8110]);
+
+        let mut processor = processor(&code);
+
+        set(&mut processor, 4, 4);
+        set(&mut processor, 8, 8);
+        set(&mut processor, 14, 0x121);
+        set(&mut processor, 0, 0x20000010);
+        set(&mut processor, 1, 1);
+        set(&mut processor, 2, 2);
+        set(&mut processor, 3, 0x20000010);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 13), 0x20000ff4);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x20000008);
+
+        run(&mut processor, 1);
+        assert_eq!((reg(&processor, 5), reg(&processor, 6)), (1, 2));
+
+        set(&mut processor, 4, 0);
+        set(&mut processor, 8, 0);
+
+        run(&mut processor, 1);
+        assert_eq!((reg(&processor, 4), reg(&processor, 8)), (4, 8));
+        assert_eq!(processor.registers.pc(), 0x120);
+    }
+
+    #[test]
+    fn table_branch() {
+        // tbb [pc, r0]; table of byte offsets
+        let mut processor = processor(&[0xe8df, 0xf000, 0x0402, 0xbf00, 0xbf00, 0xbf00]);
+
+        set(&mut processor, 0, 1);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x10c);
+    }
+
+    #[test]
+    fn add_subtract_overflow() {
+        // adds r2, r0, r1; subs r3, r0, r1; adcs r0, r1
+        let mut processor = processor(&[0x1842, 0x1a43, 0x4148]);
+
+        set(&mut processor, 0, 0x7fffffff);
+        set(&mut processor, 1, 0xffffffff);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 2), 0x7ffffffe);
+        assert_eq!(nzcv(&processor), (false, false, true, false));
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 3), 0x80000000);
+        assert_eq!(nzcv(&processor), (true, false, false, true));
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0x7ffffffe);
+        assert_eq!(nzcv(&processor), (false, false, true, false));
+    }
+
+    #[test]
+    fn if_then_block() {
+        // cmp r0, #1; ite eq; moveq.w r1, #1; movne.w r1, #2; movs r2, #3
+        let mut processor = processor(&[0x2801, 0xbf0c, 0xf04f, 0x0101, 0xf04f, 0x0102, 0x2203]);
+
+        set(&mut processor, 0, 1);
+
+        run(&mut processor, 2);
+        assert!(processor.registers.psr.in_it_block());
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 1), 1);
+        assert!(!processor.registers.psr.in_it_block());
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 2), 3);
+    }
+
+    #[test]
+    fn multiply_accumulate() {
+        // mla r0, r1, r2, r3; mls r4, r1, r2, r3; mul r5, r1, r2
+        let mut processor = processor(&[0xfb01, 0x3002, 0xfb01, 0x3412, 0xfb01, 0xf502]);
+
+        set(&mut processor, 1, 7);
+        set(&mut processor, 2, 6);
+        set(&mut processor, 3, 100);
+
+        run(&mut processor, 3);
+        assert_eq!(reg(&processor, 0), 142);
+        assert_eq!(reg(&processor, 4), 58);
+        assert_eq!(reg(&processor, 5), 42);
+    }
+
+    #[test]
+    fn long_multiply() {
+        // umull r0, r1, r2, r3; smull r4, r5, r2, r3; umlal r0, r1, r2, r3; umaal r6, r7, r2, r3
+        let mut processor = processor(&[0xfba2, 0x0103, 0xfb82, 0x4503, 0xfbe2, 0x0103, 0xfbe2, 0x6763]);
+
+        set(&mut processor, 2, 0xffffffff);
+        set(&mut processor, 3, 2);
+        set(&mut processor, 6, 1);
+        set(&mut processor, 7, 1);
+
+        run(&mut processor, 2);
+        assert_eq!((reg(&processor, 0), reg(&processor, 1)), (0xfffffffe, 1));
+        assert_eq!((reg(&processor, 4), reg(&processor, 5)), (0xfffffffe, 0xffffffff));
+
+        run(&mut processor, 2);
+        assert_eq!((reg(&processor, 0), reg(&processor, 1)), (0xfffffffc, 3));
+        assert_eq!((reg(&processor, 6), reg(&processor, 7)), (0, 2));
+    }
+
+    #[test]
+    fn divide() {
+        // sdiv r0, r1, r2; udiv r3, r1, r2; sdiv r4, r1, r5; sdiv r4, r1, r5
+        let mut processor = processor(&[0xfb91, 0xf0f2, 0xfbb1, 0xf3f2, 0xfb91, 0xf4f5, 0xfb91, 0xf4f5]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::UsageFault) * 4, 0x181);
+
+        set(&mut processor, 1, -7i32 as u32);
+        set(&mut processor, 2, 2);
+        set(&mut processor, 4, 1);
+
+        run(&mut processor, 3);
+        assert_eq!(reg(&processor, 0), -3i32 as u32);
+        assert_eq!(reg(&processor, 3), 0x7ffffffc);
+        assert_eq!(reg(&processor, 4), 0);
+
+        processor.write::<u32>(0xe000ed14, 1 << 4);
+        processor.write::<u32>(0xe000ed24, 1 << 18);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.read::<u32>(reg(&processor, 13) as usize + 0x18), 0x10c);
+        assert_eq!(processor.read::<u32>(0xe000ed28), 1 << 25);
+    }
+
+    #[test]
+    fn dsp_multiply() {
+        // smlabb r0, r1, r2, r3; smulwb r4, r5, r6; smuad r7, r9, r10; smuadx r8, r9, r10; smmla r11, r5, r5, r3
+        let mut processor = processor(&[0xfb11, 0x3002, 0xfb35, 0xf406, 0xfb29, 0xf70a, 0xfb29, 0xf81a, 0xfb55, 0x3b05]);
+
+        set(&mut processor, 1, 0x8000);
+        set(&mut processor, 2, 0x8000);
+        set(&mut processor, 3, 0x7fffffff);
+        set(&mut processor, 5, 0x10000);
+        set(&mut processor, 6, 3);
+        set(&mut processor, 9, 0x00020003);
+        set(&mut processor, 10, 0x00040005);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 0xbfffffff);
+        assert!(processor.registers.psr.flag(Flag::Q));
+
+        run(&mut processor, 4);
+        assert_eq!(reg(&processor, 4), 3);
+        assert_eq!(reg(&processor, 7), 23);
+        assert_eq!(reg(&processor, 8), 22);
+        assert_eq!(reg(&processor, 11), 0x80000000);
+    }
+
+    #[test]
+    fn dsp_simd() {
+        // uadd8 r0, r1, r2; sel r3, r1, r2; qadd r4, r5, r6; ssat r7, #8, r8; pkhbt r9, r1, r2, lsl #16; usad8 r10, r1, r2; sxtb16 r11, r12
+        let mut processor = processor(&[
+            0xfa81, 0xf042, 0xfaa1, 0xf382, 0xfa86, 0xf485, 0xf308, 0x0707, 0xeac1, 0x4902, 0xfb71, 0xfa02, 0xfa2f, 0xfb8c,
+        ]);
+
+        set(&mut processor, 1, 0xff0180ff);
+        set(&mut processor, 2, 0x01017f01);
+        set(&mut processor, 5, 0x7fffffff);
+        set(&mut processor, 6, 1);
+        set(&mut processor, 8, -512i32 as u32);
+        set(&mut processor, 12, 0x00800080);
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 0), 0x0002ff00);
+        assert_eq!(processor.registers.psr.ge(), 0b1001);
+        assert_eq!(reg(&processor, 3), 0xff017fff);
+        assert!(!processor.registers.psr.flag(Flag::Q));
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 4), 0x7fffffff);
+        assert_eq!(reg(&processor, 7), -128i32 as u32);
+        assert!(processor.registers.psr.flag(Flag::Q));
+
+        run(&mut processor, 3);
+        assert_eq!(reg(&processor, 9), 0x7f0180ff);
+        assert_eq!(reg(&processor, 10), 509);
+        assert_eq!(reg(&processor, 11), 0xff80ff80);
+    }
+
+    #[test]
+    fn floating_point() {
+        // vmov.f32 s0, #1.0; vmov s1, r0; vadd.f32 s2, s0, s1; vdiv.f32 s3, s2, s1; vcvt.s32.f32 s4, s2; vmov r1, s4
+        // vcmpe.f32 s0, s1; vmrs APSR_nzcv, fpscr; vpush {s0-s1}; vpop {s2-s3}
+        let mut processor = processor(&[
+            0xeeb7, 0x0a00, 0xee00, 0x0a90, 0xee30, 0x1a20, 0xeec1, 0x1a20, 0xeebd, 0x2ac1, 0xee12, 0x1a10,
+            0xeeb4, 0x0ae0, 0xeef1, 0xfa10, 0xed2d, 0x0a02, 0xecbd, 0x1a02,
+        ]);
+
+        processor.write::<u32>(0xe000ed88, 0x00f00000);
+
+        set(&mut processor, 0, 3.0f32.to_bits());
+
+        run(&mut processor, 6);
+        assert_eq!(f32::from_bits(processor.registers.fp[2]), 4.0);
+        assert_eq!(f32::from_bits(processor.registers.fp[3]), 4.0 / 3.0);
+        assert_eq!(reg(&processor, 1), 4);
+
+        run(&mut processor, 2);
+        assert_eq!(nzcv(&processor), (true, false, false, false));
+        assert_eq!(processor.registers.fpscr.value & 0x1f, 1 << 4);
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 13), 0x20001000);
+        assert_eq!(f32::from_bits(processor.registers.fp[2]), 1.0);
+        assert_eq!(f32::from_bits(processor.registers.fp[3]), 3.0);
+    }
+
+    #[test]
+    fn half_precision() {
+        // vmov s1, r0; vcvtt.f16.f32 s0, s1; vcvtt.f32.f16 s2, s0
+        let mut processor = processor(&[0xee00, 0x0a90, 0xeeb3, 0x0ae0, 0xeeb2, 0x1ac0]);
+
+        processor.write::<u32>(0xe000ed88, 0x00f00000);
+        processor.registers.fp[0] = 0x1234;
+
+        set(&mut processor, 0, 1.5f32.to_bits());
+
+        run(&mut processor, 3);
+        assert_eq!(processor.registers.fp[0], 0x3e001234);
+        assert_eq!(f32::from_bits(processor.registers.fp[2]), 1.5);
+    }
+
+    #[test]
+    fn fpu_disabled() {
+        // vadd.f32 s0, s1, s2 with cp10 and cp11 denied
+        let mut processor = processor(&[0xee30, 0x0a81]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::UsageFault) * 4, 0x181);
+        processor.write::<u32>(0xe000ed24, 1 << 18);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.read::<u32>(reg(&processor, 13) as usize + 0x18), 0x100);
+        assert_eq!(processor.read::<u32>(0xe000ed28), 1 << 19);
+        assert_eq!(processor.read::<u32>(0xe000ed24), (1 << 18) | (1 << 3));
+    }
+
+    #[test]
+    fn bit_manipulation() {
+        // bfi r0, r1, #4, #8; bfc r0, #0, #4; ubfx r2, r0, #4, #8; sbfx r3, r0, #4, #8; clz r4, r2; rbit r5, r2
+        let mut processor = processor(&[0xf361, 0x100b, 0xf36f, 0x0003, 0xf3c0, 0x1207, 0xf340, 0x1307, 0xfab2, 0xf482, 0xfa92, 0xf5a2]);
+
+        set(&mut processor, 0, 0xffffffff);
+        set(&mut processor, 1, 0x000000a5);
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 0), 0xfffffa50);
+
+        run(&mut processor, 4);
+        assert_eq!(reg(&processor, 2), 0xa5);
+        assert_eq!(reg(&processor, 3), 0xffffffa5);
+        assert_eq!(reg(&processor, 4), 24);
+        assert_eq!(reg(&processor, 5), 0xa5000000);
+    }
+
+    #[test]
+    fn byte_reverse_and_extend() {
+        // rev r1, r0; rev16 r2, r0; revsh r3, r0; sxtb r4, r0; uxth r5, r0; sxtah r6, r1, r0, ror #8
+        let mut processor = processor(&[0xba01, 0xba42, 0xbac3, 0xb244, 0xb285, 0xfa01, 0xf690]);
+
+        set(&mut processor, 0, 0x12348680);
+
+        run(&mut processor, 6);
+        assert_eq!(reg(&processor, 1), 0x80863412);
+        assert_eq!(reg(&processor, 2), 0x34128086);
+        assert_eq!(reg(&processor, 3), 0xffff8086);
+        assert_eq!(reg(&processor, 4), 0xffffff80);
+        assert_eq!(reg(&processor, 5), 0x8680);
+        assert_eq!(reg(&processor, 6), 0x80863412u32.wrapping_add(0x3486));
+    }
+
+    #[test]
+    fn special_registers() {
+        // movs r0, #1; msr PRIMASK, r0; mrs r1, PRIMASK; cpsie i; mrs r2, PRIMASK; msr CONTROL, r0; cpsid i; mrs r3, CONTROL; msr PRIMASK, r0
+        let mut processor = processor(&[0x2001, 0xf380, 0x8810, 0xf3ef, 0x8110, 0xb662, 0xf3ef, 0x8210, 0xf380, 0x8814, 0xb672, 0xf3ef, 0x8314, 0xf380, 0x8810]);
+
+        run(&mut processor, 3);
+        assert_eq!(reg(&processor, 1), 1);
+        assert!(processor.registers.primask);
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 2), 0);
+
+        run(&mut processor, 4);
+        assert!(processor.registers.control.private);
+        assert!(!processor.registers.primask);
+        assert_eq!(reg(&processor, 3), 1);
+    }
+
+    #[test]
+    fn faultmask_in_nmi() {
+        // nop with msr FAULTMASK, r0; cpsie f as the nmi handler at 0x180
+        let mut processor = processor(&[0xbf00]);
+
+        processor.write::<u16>(0x180, 0xf380);
+        processor.write::<u16>(0x182, 0x8813);
+        processor.write::<u16>(0x184, 0xb661);
+        processor.write::<u32>(Into::<usize>::into(Exception::Nmi) * 4, 0x181);
+        processor.write::<u32>(0xe000ed04, 1 << 31);
+
+        processor.registers.faultmask = true;
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.registers.pc(), 0x186);
+        assert!(processor.registers.faultmask);
+    }
+
+    #[test]
+    fn process_stack() {
+        // movs r0, #2; msr PSP, r1; msr CONTROL, r0; mrs r2, MSP; msr BASEPRI, r3; msr BASEPRI_MAX, r4; msr BASEPRI_MAX, r5; mrs r6, BASEPRI
+        let mut processor = processor(&[0x2002, 0xf381, 0x8809, 0xf380, 0x8814, 0xf3ef, 0x8208, 0xf383, 0x8811, 0xf384, 0x8812, 0xf385, 0x8812, 0xf3ef, 0x8611]);
+
+        set(&mut processor, 1, 0x20000800);
+        set(&mut processor, 3, 0x40);
+        set(&mut processor, 4, 0x80);
+        set(&mut processor, 5, 0x3f);
+
+        run(&mut processor, 4);
+        assert_eq!(reg(&processor, 13), 0x20000800);
+        assert_eq!(reg(&processor, 2), 0x20001000);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.registers.basepri, 0x40);
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 6), 0x20);
+    }
+
+    #[test]
+    fn masked_exception_stays_pending() {
+        // cpsid i; str r2, [r0]; nop; cpsie i; nop with bx lr as pendsv at 0x180
+        let mut processor = processor(&[0xb672, 0x6002, 0xbf00, 0xb662, 0xbf00]);
+
+        processor.write::<u16>(0x180, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::PendSV) * 4, 0x181);
+
+        set(&mut processor, 0, 0xe000ed04);
+        set(&mut processor, 2, 1 << 28);
+
+        run(&mut processor, 3);
+        assert_eq!(processor.mode, Mode::Thread);
+        assert_eq!(processor.registers.pc(), 0x106);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.mode, Mode::Handle);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.read::<u32>(reg(&processor, 13) as usize + 0x18), 0x108);
+    }
+
+    #[test]
+    fn base_priority_masking() {
+        // msr BASEPRI, r3; str r2, [r0]; msr BASEPRI, r1 with bx lr as pendsv at 0x180
+        let mut processor = processor(&[0xf383, 0x8811, 0x6002, 0xf381, 0x8811]);
+
+        processor.write::<u16>(0x180, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::PendSV) * 4, 0x181);
+        processor.write::<u8>(0xe000ed22, 0x80);
+
+        set(&mut processor, 0, 0xe000ed04);
+        set(&mut processor, 2, 1 << 28);
+        set(&mut processor, 3, 0x80);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.mode, Mode::Thread);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.mode, Mode::Handle);
+        assert_eq!(processor.execution_priority(), 0x80);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.mode, Mode::Thread);
+        assert_eq!(processor.execution_priority(), 256);
+    }
+
+    #[test]
+    fn active_exception_blocks_equal_priority() {
+        // svc #0 with str r2, [r0]; bx lr as the handler at 0x180 and bx lr as pendsv at 0x1a0
+        let mut processor = processor(&[0xdf00, 0xbf00]);
+
+        for (offset, halfword) in [0x6002, 0x4770].iter().enumerate() {
+            processor.write::<u16>(0x180 + offset * 2, *halfword);
+        }
+
+        processor.write::<u16>(0x1a0, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::SVCall) * 4, 0x181);
+        processor.write::<u32>(Into::<usize>::into(Exception::PendSV) * 4, 0x1a1);
+
+        set(&mut processor, 0, 0xe000ed04);
+        set(&mut processor, 2, 1 << 28);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.registers.pc(), 0x182);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x1a0);
+        assert_eq!(processor.mode, Mode::Handle);
+    }
+
+    #[test]
+    fn forced_hard_fault() {
+        // svc #0 with svc #1 as the handler at 0x180 and the hardfault handler at 0x1a0
+        let mut processor = processor(&[0xdf00]);
+
+        processor.write::<u16>(0x180, 0xdf01);
+        processor.write::<u32>(Into::<usize>::into(Exception::SVCall) * 4, 0x181);
+        processor.write::<u32>(Into::<usize>::into(Exception::HardFault) * 4, 0x1a1);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.registers.pc(), 0x1a0);
+        assert_eq!(processor.read::<u32>(0xe000ed2c), 1 << 30);
+
+        processor.write::<u32>(0xe000ed2c, 1 << 30);
+        assert_eq!(processor.read::<u32>(0xe000ed2c), 0);
+    }
+
+    #[test]
+    fn precise_bus_fault() {
+        // ldr r1, [r0]; ldr r1, [r0] with the busfault handler at 0x180 and the hardfault handler at 0x1a0
+        let mut processor = processor(&[0x6801, 0x6801]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::BusFault) * 4, 0x181);
+        processor.write::<u32>(Into::<usize>::into(Exception::HardFault) * 4, 0x1a1);
+
+        set(&mut processor, 0, 0x60000000);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x1a0);
+        assert_eq!(processor.read::<u32>(0xe000ed28), (1 << 15) | (1 << 9));
+        assert_eq!(processor.read::<u32>(0xe000ed38), 0x60000000);
+
+        let mut processor = self::processor(&[0x6801]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::BusFault) * 4, 0x181);
+        processor.write::<u32>(0xe000ed24, 1 << 17);
+
+        set(&mut processor, 0, 0x60000000);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.read::<u32>(reg(&processor, 13) as usize + 0x18), 0x100);
+        assert_eq!(processor.read::<u32>(0xe000ed2c), 0);
+    }
+
+    #[test]
+    fn stacking_wrap() {
+        // ldr r1, [r0] with msp at the bottom of the address space and the busfault handler at 0x180
+        let mut processor = processor(&[0x6801]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::BusFault) * 4, 0x181);
+        processor.write::<u32>(0xe000ed24, 1 << 17);
+
+        set(&mut processor, 0, 0x60000000);
+        set(&mut processor, 13, 0);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 13), 0xffffffe0);
+        assert_eq!(processor.read::<u32>(0xe000ed28) & (1 << 12), 1 << 12);
+    }
+
+    #[test]
+    fn bus_fault_abort() {
+        // ldr r1, [r0, #4]! with the busfault handler at 0x180
+        let mut processor = processor(&[0xf850, 0x1f04]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::BusFault) * 4, 0x181);
+        processor.write::<u32>(0xe000ed24, 1 << 17);
+
+        set(&mut processor, 0, 0x5ffffffc);
+        set(&mut processor, 1, 0x1234);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!((reg(&processor, 0), reg(&processor, 1)), (0x5ffffffc, 0x1234));
+
+        // ldm r0!, {r1, r2, r3} where only the first word is mapped
+        let mut processor = self::processor(&[0xc80e]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::BusFault) * 4, 0x181);
+        processor.write::<u32>(0xe000ed24, 1 << 17);
+        processor.write::<u32>(0x20003ff8, 0xdead);
+
+        set(&mut processor, 0, 0x20003ff8);
+        set(&mut processor, 1, 0x1234);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!((reg(&processor, 0), reg(&processor, 1)), (0x20003ff8, 0x1234));
+
+        // pop {r1, pc} where the pc word is unmapped
+        let mut processor = self::processor(&[0xbd02]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::BusFault) * 4, 0x181);
+        processor.write::<u32>(0xe000ed24, 1 << 17);
+        processor.write::<u32>(0x20003ff8, 0xdead);
+
+        set(&mut processor, 1, 0x1234);
+        set(&mut processor, 13, 0x20003ff8);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(reg(&processor, 1), 0x1234);
+        assert_eq!(reg(&processor, 13), 0x20003ff8 - 0x20);
+        assert_eq!(processor.read::<u32>(0x20003ff8 - 0x20 + 0x18), 0x100);
+    }
+
+    #[test]
+    fn fault_in_it_block() {
+        // cmp r0, #1; ite eq; udiveq r2, r1, r3; movne r2, #7 with bx lr as the usagefault handler at 0x180
+        let mut processor = processor(&[0x2801, 0xbf0c, 0xfbb1, 0xf2f3, 0x2207]);
+
+        processor.write::<u16>(0x180, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::UsageFault) * 4, 0x181);
+        processor.write::<u32>(0xe000ed24, 1 << 18);
+        processor.write::<u32>(0xe000ed14, (1 << 9) | (1 << 4));
+
+        set(&mut processor, 0, 1);
+        set(&mut processor, 1, 10);
+
+        run(&mut processor, 3);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.read::<u32>(reg(&processor, 13) as usize + 0x18), 0x104);
+
+        // the handler returns without the trap and the division is retried under the same condition
+        processor.write::<u32>(0xe000ed14, 1 << 9);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x104);
+        assert_eq!(processor.registers.psr.it_condition(), instruction::Condition::Eq);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.registers.pc(), 0x10a);
+        assert_eq!(reg(&processor, 2), 0);
+    }
+
+    #[test]
+    fn invalid_state() {
+        // nop with the thumb bit cleared and the usagefault handler at 0x180
+        let mut processor = processor(&[0xbf00]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::UsageFault) * 4, 0x181);
+        processor.write::<u32>(0xe000ed24, 1 << 18);
+        processor.registers.psr.unset(24);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.read::<u32>(0xe000ed28), 1 << 17);
+    }
+
+    #[test]
+    fn undefined_instruction() {
+        // udf #0 with the usagefault handler at 0x180 and the hardfault handler at 0x1a0
+        let mut processor = processor(&[0xde00]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::UsageFault) * 4, 0x181);
+        processor.write::<u32>(Into::<usize>::into(Exception::HardFault) * 4, 0x1a1);
+
+        let mut forced = processor.clone();
+
+        processor.write::<u32>(0xe000ed24, 1 << 18);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.read::<u32>(0xe000ed28), 1 << 16);
+
+        run(&mut forced, 1);
+        assert_eq!(forced.registers.pc(), 0x1a0);
+        assert_eq!(forced.read::<u32>(0xe000ed2c), 1 << 30);
+    }
+
+    #[test]
+    fn stop_on_undefined() {
+        // nop; udf #0
+        let mut processor = processor(&[0xbf00, 0xde00]);
+
+        processor.undefined = UndefinedPolicy::Stop;
+
+        assert_eq!(processor.step(), None);
+        assert_eq!(processor.step(), Some(Stop::Undefined { addr: 0x102, opcode: 0xde00 }));
+        assert_eq!(processor.registers.pc(), 0x102);
+        assert_eq!(processor.read::<u32>(0xe000ed28), 0);
+    }
+
+    #[test]
+    fn lockup() {
+        // udf #0 with udf #0 as the hardfault handler at 0x180 and the nmi handler at 0x1a0
+        let mut processor = processor(&[0xde00]);
+
+        processor.write::<u16>(0x180, 0xde00);
+        processor.write::<u32>(Into::<usize>::into(Exception::HardFault) * 4, 0x181);
+        processor.write::<u32>(Into::<usize>::into(Exception::Nmi) * 4, 0x1a1);
+
+        assert_eq!(processor.step(), None);
+        assert_eq!(processor.registers.pc(), 0x180);
+
+        assert_eq!(processor.step(), Some(Stop::Lockup));
+        assert_eq!(processor.registers.pc(), 0xfffffffe);
+        assert!(processor.locked_up());
+
+        assert_eq!(processor.step(), Some(Stop::Lockup));
+        assert_eq!(processor.registers.pc(), 0xfffffffe);
+        assert_eq!(processor.registers.get(15, processor.mode), 0x2);
+
+        // the frontend may still look at the lockup address without the pc overflowing
+        let mut inspect = processor.clone();
+        let inst = inspect.fetch();
+
+        inspect.skip(&inst);
+        assert_eq!(inst.addr, 0xfffffffe);
+
+        processor.write::<u32>(0xe000ed04, 1 << 31);
+
+        assert_eq!(processor.step(), None);
+        assert_eq!(processor.registers.pc(), 0x1a0);
+        assert!(!processor.locked_up());
+    }
+
+    #[test]
+    fn process_stack_return() {
+        // nop; nop; nop with bx lr as the pendsv handler at 0x180 and a second thread frame at 0x20000400
+        let mut processor = processor(&[0xbf00, 0xbf00, 0xbf00]);
+
+        processor.write::<u16>(0x180, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::PendSV) * 4, 0x181);
+
+        processor.registers.sp.psp = 0x20000800;
+        processor.registers.control.stack = true;
+
+        processor.write::<u32>(0xe000ed04, 1 << 28);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(reg(&processor, 14), 0xfffffffd);
+        assert_eq!(processor.registers.sp.psp, 0x200007e0);
+        assert_eq!(processor.registers.sp.msp, 0x20001000);
+        assert!(!processor.registers.control.stack);
+
+        processor.write::<u32>(0x20000400, 0x1234);
+        processor.write::<u32>(0x20000418, 0x104);
+        processor.write::<u32>(0x2000041c, 1 << 24);
+        processor.registers.sp.psp = 0x20000400;
+
+        run(&mut processor, 1);
+        assert_eq!(processor.mode, Mode::Thread);
+        assert_eq!(processor.registers.pc(), 0x104);
+        assert_eq!(reg(&processor, 0), 0x1234);
+        assert_eq!(processor.registers.sp.psp, 0x20000420);
+        assert!(processor.registers.control.stack);
+    }
+
+    #[test]
+    fn invalid_exception_return() {
+        // nop with bx r1 as the pendsv handler at 0x180 and the usagefault handler at 0x1a0
+        let mut processor = processor(&[0xbf00]);
+
+        processor.write::<u16>(0x180, 0x4708);
+        processor.write::<u32>(Into::<usize>::into(Exception::PendSV) * 4, 0x181);
+        processor.write::<u32>(Into::<usize>::into(Exception::UsageFault) * 4, 0x1a1);
+        processor.write::<u32>(0xe000ed24, 1 << 18);
+        processor.write::<u32>(0xe000ed04, 1 << 28);
+
+        // returning to handler mode without a nested activation is an integrity failure
+        set(&mut processor, 1, 0xfffffff1);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.registers.pc(), 0x1a0);
+        assert_eq!(processor.read::<u32>(0xe000ed28), 1 << 18);
+    }
+
+    #[test]
+    fn invalid_stacked_ipsr() {
+        // nop with str r1, [sp, #28]; bx lr as the pendsv handler at 0x180 and the usagefault handler at 0x1a0
+        let mut processor = processor(&[0xbf00]);
+
+        processor.write::<u16>(0x180, 0x9107);
+        processor.write::<u16>(0x182, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::PendSV) * 4, 0x181);
+        processor.write::<u32>(Into::<usize>::into(Exception::UsageFault) * 4, 0x1a1);
+        processor.write::<u32>(0xe000ed24, 1 << 18);
+        processor.write::<u32>(0xe000ed04, 1 << 28);
+
+        // the frame claims thread mode was running exception 14
+        set(&mut processor, 1, (1 << 24) | 14);
+
+        run(&mut processor, 3);
+        assert_eq!(processor.registers.pc(), 0x1a0);
+        assert_eq!(processor.read::<u32>(0xe000ed28), 1 << 18);
+        assert_eq!(reg(&processor, 13), 0x20000fe0);
+        assert_eq!(reg(&processor, 14), 0xfffffff9);
+        assert_eq!(processor.read::<u32>(0x20000fe0 + 0x18), 0x102);
+    }
+
+    #[test]
+    fn tail_chaining() {
+        // nop with bx lr as the handler of irq 1 at 0x180 and irq 2 at 0x1a0
+        let mut processor = processor(&[0xbf00, 0xbf00]);
+
+        processor.write::<u16>(0x180, 0x4770);
+        processor.write::<u16>(0x1a0, 0x4770);
+        processor.write::<u32>((16 + 1) * 4, 0x181);
+        processor.write::<u32>((16 + 2) * 4, 0x1a1);
+        processor.write::<u8>(0xe000e402, 0x20);
+        processor.write::<u32>(0xe000e100, 0b110);
+        processor.write::<u32>(0xe000e200, 0b110);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.cycles, 1 + 12);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x1a0);
+        assert_eq!(processor.registers.sp.msp, 0x20000fe0);
+        assert_eq!(reg(&processor, 14), 0xfffffff9);
+        assert_eq!(processor.cycles, 1 + 12 + 3 + 6);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.mode, Mode::Thread);
+        assert_eq!(processor.registers.pc(), 0x102);
+        assert_eq!(processor.registers.sp.msp, 0x20001000);
+        assert_eq!(processor.cycles, 1 + 12 + 3 + 6 + 3 + 10);
+    }
+
+    #[test]
+    fn late_arrival() {
+        // nop with bx lr as the systick handler at 0x180 and the pendsv handler at 0x1a0
+        let mut processor = processor(&[0xbf00, 0xbf00]);
+
+        processor.write::<u16>(0x180, 0x4770);
+        processor.write::<u16>(0x1a0, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::SysTick) * 4, 0x181);
+        processor.write::<u32>(Into::<usize>::into(Exception::PendSV) * 4, 0x1a1);
+        processor.write::<u8>(0xe000ed22, 0xe0);
+        processor.write::<u32>(0xe000e014, 2);
+        processor.write::<u32>(0xe000e010, 0b011);
+        processor.write::<u32>(0xe000ed04, 1 << 28);
+
+        // systick wraps while pendsv is being stacked and claims the entry instead
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.read::<u32>(0xe000ed04) & (1 << 28), 1 << 28);
+        assert_eq!(processor.cycles, 1 + 12);
+
+        processor.write::<u32>(0xe000e010, 0);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x1a0);
+        assert_eq!(processor.cycles, 1 + 12 + 3 + 6);
+    }
+
+    #[derive(Clone)]
+    struct Latch {
+        value: u32,
+    }
+
+    impl Peripheral for Latch {
+        fn read(&mut self, _offset: usize, _size: usize) -> u32 {
+            self.value
+        }
+
+        fn write(&mut self, _offset: usize, _size: usize, value: u32) {
+            self.value = value;
+        }
+
+        fn interrupt(&self) -> bool {
+            self.value != 0
+        }
+    }
+
+    #[test]
+    fn attached_peripheral() {
+        // str r1, [r0]; ldr r3, [r0] with str r2, [r0]; bx lr as the handler of irq 8 at 0x180
+        let mut processor = processor(&[0x6001, 0x6803]);
+
+        processor.write::<u16>(0x180, 0x6002);
+        processor.write::<u16>(0x182, 0x4770);
+        processor.write::<u32>((16 + 8) * 4, 0x181);
+        processor.write::<u32>(0xe000e100, 1 << 8);
+
+        processor.attach(0x40008000..0x40009000, Some(8), Box::new(Latch { value: 0 }));
+
+        set(&mut processor, 0, 0x40008000);
+        set(&mut processor, 1, 0x42);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.mode, Mode::Thread);
+        assert_eq!(processor.registers.pc(), 0x102);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 3), 0);
+        assert_eq!(processor.read::<u32>(0xe000ed28), 0);
+    }
+
+    #[test]
+    fn machine_memory() {
+        let machine = Machine::parse(r#"
+            vtor = 0x200
+
+            [[memory]]
+            name = "flash"
+            base = 0x0
+            size = 0x1000
+            permissions = "r-x"
+
+            [[memory]]
+            name = "ram"
+            base = 0x20000000
+            size = 0x1000
+            permissions = "rw-"
+        "#, std::path::Path::new(".")).unwrap();
+
+        // str r1, [r0] at 0x100 with the vector table at 0x200
+        let mut processor = Processor::with_machine(&machine);
+
+        processor.flash_data(0x100, &[0x01, 0x60]);
+        processor.flash_data(0x200, &[0x00, 0x10, 0x00, 0x20, 0x01, 0x01, 0x00, 0x00]);
+
+        processor.reset();
+        assert_eq!(processor.registers.pc(), 0x100);
+        assert_eq!(reg(&processor, 13), 0x20001000);
+
+        set(&mut processor, 0, 0x100);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.read::<u16>(0x100), 0x6001);
+        assert_eq!(processor.read::<u32>(0xe000ed28), (1 << 15) | (1 << 9));
+    }
+
+    #[test]
+    fn code_ram_alias() {
+        let mut processor = Processor::with_machine(&Machine::nrf52833());
+
+        // movs r0, #42 executed from the code ram alias of 0x20000100
+        processor.write::<u16>(0x20000100, 0x202a);
+
+        processor.registers.set(15, |_| 0x00800100, processor.mode);
+        processor.registers.set(13, |_| 0x20001000, processor.mode);
+        processor.registers.psr.set(24);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 0), 42);
+        assert_eq!(processor.registers.pc(), 0x00800102);
+
+        processor.write::<u32>(0x00800200, 0x12345678);
+        assert_eq!(processor.read::<u32>(0x20000200), 0x12345678);
+        assert_eq!(processor.read::<u32>(0x10000100), 0x52833);
+
+        // flash is only written through the nvmc
+        processor.write::<u32>(0x0, 0x12345678);
+        assert_eq!(processor.read::<u32>(0x0), 0);
+    }
+
+    #[test]
+    fn bit_band() {
+        // ldr r1, [r0]; str r2, [r0]
+        let mut processor = processor(&[0x6801, 0x6002]);
+
+        processor.write::<u32>(0x20000010, 0x80);
+
+        processor.attach(0x40008000..0x40009000, None, Box::new(Latch { value: 0x1 }));
+
+        set(&mut processor, 0, 0x22000000 + 0x10 * 32 + 7 * 4);
+        set(&mut processor, 2, 0);
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 1), 1);
+        assert_eq!(processor.read::<u32>(0x20000010), 0);
+
+        processor.write::<u8>(0x22000000 + 0x13 * 32 + 2 * 4, 1);
+        assert_eq!(processor.read::<u32>(0x20000010), 0x04000000);
+        assert_eq!(processor.read::<u16>(0x22000000 + 0x13 * 32 + 2 * 4), 1);
+
+        processor.write::<u32>(0x42000000 + 0x8000 * 32 + 5 * 4, 0xff);
+        assert_eq!(processor.read::<u32>(0x40008000), 0x21);
+        assert_eq!(processor.read::<u32>(0x42000000 + 0x8000 * 32), 1);
+        assert_eq!(processor.read::<u32>(0x42000000 + 0x8000 * 32 + 4), 0);
+    }
+
+    #[test]
+    fn bit_band_memory() {
+        let mut machine = Machine::flat();
+
+        // descriptions with memory in the alias window are rejected, a hand built machine still gets its memory
+        machine.memory.push(crate::machine::MemoryRegion {
+            name: String::from("sram1"),
+            base: 0x22000000,
+            size: 0x1000,
+            permissions: crate::memory::Permissions::all(),
+            file: None,
+            contents: Vec::new(),
+        });
+
+        let mut processor = Processor::with_machine(&machine);
+
+        processor.write::<u32>(0x22000000, 0x12345678);
+        assert_eq!(processor.read::<u32>(0x22000000), 0x12345678);
+        assert_eq!(processor.read::<u32>(0x20000000), 0);
+    }
+
+    #[test]
+    fn unaligned_access() {
+        // ldr r1, [r0]; ldr r1, [r0] with the usagefault handler at 0x180
+        let mut processor = processor(&[0x6801, 0x6801]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::UsageFault) * 4, 0x181);
+        processor.write::<u32>(0xe000ed24, 1 << 18);
+        processor.write::<u32>(0x20000000, 0x11223344);
+        processor.write::<u32>(0x20000004, 0x55667788);
+
+        set(&mut processor, 0, 0x20000002);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 1), 0x77881122);
+
+        processor.write::<u32>(0xe000ed14, (1 << 9) | (1 << 3));
+        set(&mut processor, 1, 0);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(reg(&processor, 1), 0);
+        assert_eq!(processor.read::<u32>(0xe000ed28), 1 << 24);
+    }
+
+    #[test]
+    fn multiple_address_wrap() {
+        // stmdb r0!, {r1} from the bottom of the address space with the busfault handler at 0x180
+        let mut processor = processor(&[0xe920, 0x0002]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::BusFault) * 4, 0x181);
+        processor.write::<u32>(0xe000ed24, 1 << 17);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.read::<u32>(0xe000ed38), 0xfffffffc);
+        assert_eq!(reg(&processor, 0), 0);
+
+        // ldm r0!, {r1, r2} across the top of the address space
+        let mut processor = self::processor(&[0xc806]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::BusFault) * 4, 0x181);
+        processor.write::<u32>(0xe000ed24, 1 << 17);
+
+        set(&mut processor, 0, 0xfffffffc);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.read::<u32>(0xe000ed38), 0xfffffffc);
+        assert_eq!(reg(&processor, 0), 0xfffffffc);
+    }
+
+    #[test]
+    fn unaligned_multiple() {
+        // ldm r0!, {r1} with the usagefault handler at 0x180
+        let mut processor = processor(&[0xc802]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::UsageFault) * 4, 0x181);
+        processor.write::<u32>(0xe000ed24, 1 << 18);
+
+        set(&mut processor, 0, 0x20000002);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(reg(&processor, 0), 0x20000002);
+        assert_eq!(processor.read::<u32>(0xe000ed28), 1 << 24);
+    }
+
+    #[test]
+    fn peripheral_interrupt() {
+        // nop; nop with bx lr as the handler of irq 6 at 0x180
+        let mut processor = processor(&[0xbf00, 0xbf00]);
+
+        processor.write::<u16>(0x180, 0x4770);
+        processor.write::<u32>((16 + 6) * 4, 0x181);
+        processor.write::<u8>(0xe000e406, 0xc0);
+        processor.write::<u32>(0xe000e200, 1 << 6);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.mode, Mode::Thread);
+        assert_eq!(processor.read::<u32>(0xe000e200), 1 << 6);
+
+        processor.write::<u32>(0xe000e100, 1 << 6);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.read::<u32>(0xe000e300), 1 << 6);
+        assert_eq!(processor.read::<u8>(0xe000e406), 0xc0);
+        assert_eq!(processor.execution_priority(), 0xc0);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.mode, Mode::Thread);
+        assert_eq!(processor.read::<u32>(0xe000e300), 0);
+    }
+
+    #[test]
+    fn pend_sv_from_icsr() {
+        // nop; nop with nop; bx lr as the handler at 0x180
+        let mut processor = processor(&[0xbf00, 0xbf00]);
+
+        for (offset, halfword) in [0xbf00, 0x4770].iter().enumerate() {
+            processor.write::<u16>(0x180 + offset * 2, *halfword);
+        }
+
+        processor.write::<u32>(Into::<usize>::into(Exception::PendSV) * 4, 0x181);
+        processor.write::<u32>(0xe000ed04, 1 << 28);
+
+        assert_eq!(processor.read::<u32>(0xe000ed04), (1 << 28) | (14 << 12) | (1 << 11));
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.read::<u32>(0xe000ed04), (1 << 11) | 14);
+    }
+
+    #[test]
+    fn priority_grouping() {
+        // svc #0 with ldr r1, [r0]; str r2, [r0]; nop; bx lr as the handler at 0x180 and bx lr as pendsv at 0x1a0
+        let mut processor = processor(&[0xdf00, 0xbf00]);
+
+        for (offset, halfword) in [0x6801, 0x6002, 0xbf00, 0x4770].iter().enumerate() {
+            processor.write::<u16>(0x180 + offset * 2, *halfword);
+        }
+
+        processor.write::<u16>(0x1a0, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::SVCall) * 4, 0x181);
+        processor.write::<u32>(Into::<usize>::into(Exception::PendSV) * 4, 0x1a1);
+
+        processor.write::<u8>(0xe000ed1f, 0x60);
+        processor.write::<u8>(0xe000ed22, 0x5f);
+        processor.write::<u32>(0xe000ed0c, 0x05fa0500);
+
+        assert_eq!(processor.read::<u32>(0xe000ed1c), 0x60000000);
+        assert_eq!(processor.read::<u32>(0xe000ed20), 0x00400000);
+
+        set(&mut processor, 0, 0xe000ed04);
+        set(&mut processor, 2, 1 << 28);
+
+        run(&mut processor, 3);
+        assert_eq!(reg(&processor, 1) & 0x1ff, 11);
+        assert_eq!(processor.registers.pc(), 0x184);
+
+        run(&mut processor, 2);
+        assert_eq!(processor.registers.pc(), 0x1a0);
+        assert_eq!(processor.mode, Mode::Handle);
+    }
+
+    #[test]
+    fn system_reset_request() {
+        // nop; nop with the reset vector pointing back at 0x100
+        let mut processor = processor(&[0xbf00, 0xbf00]);
+
+        processor.write::<u32>(0, 0x20000800);
+        processor.write::<u32>(4, 0x101);
+
+        set(&mut processor, 0, 42);
+
+        run(&mut processor, 1);
+        processor.write::<u32>(0xe000ed0c, 0x05fa0004);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x100);
+        assert_eq!(reg(&processor, 0), 0);
+        assert_eq!(reg(&processor, 13), 0x20000800);
+    }
+
+    #[test]
+    fn systick_lanes() {
+        let mut processor = processor(&[]);
+
+        processor.write::<u8>(0xe000e014, 0x34);
+        processor.write::<u8>(0xe000e015, 0x12);
+        processor.write::<u16>(0xe000e016, 0xff);
+
+        assert_eq!(processor.read::<u32>(0xe000e014), 0xff1234);
+        assert_eq!(processor.read::<u8>(0xe000e015), 0x12);
+        assert_eq!(processor.read::<u16>(0xe000e016), 0xff);
+        assert_eq!(processor.read::<u8>(0xe000e01f), 0xc0);
+
+        processor.write::<u8>(0xe000e010, 0b001);
+        assert_eq!(processor.read::<u32>(0xe000e010), 0b101);
+    }
+
+    #[test]
+    fn systick_interrupt() {
+        // nop; nop; nop; nop; nop with bx lr as the handler at 0x180
+        let mut processor = processor(&[0xbf00, 0xbf00, 0xbf00, 0xbf00, 0xbf00]);
+
+        processor.write::<u16>(0x180, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::SysTick) * 4, 0x181);
+        processor.write::<u32>(0xe000e014, 3);
+        processor.write::<u32>(0xe000e010, 0b011);
+
+        run(&mut processor, 3);
+        assert_eq!(processor.mode, Mode::Thread);
+        assert_eq!(processor.read::<u32>(0xe000e018), 1);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.cycles, 4 + 12);
+        assert_eq!(processor.read::<u32>(0xe000e010), 0x10007);
+
+        // the counter wrapped again while stacking so the return tail-chains back into the handler
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert_eq!(processor.cycles, 4 + 12 + 3 + 6);
+    }
+
+    #[test]
+    fn exclusive_monitor() {
+        // ldrex r0, [r1]; strex r2, r3, [r1]; strex r2, r3, [r1]; ldrexb r0, [r1]; clrex; strexb r2, r3, [r1]
+        let mut processor = processor(&[0xe851, 0x0f00, 0xe841, 0x3200, 0xe841, 0x3200, 0xe8d1, 0x0f4f, 0xf3bf, 0x8f2f, 0xe8c1, 0x3f42]);
+
+        set(&mut processor, 1, 0x20000000);
+        set(&mut processor, 3, 0x12345678);
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 2), 0);
+        assert_eq!(processor.read::<u32>(0x20000000), 0x12345678);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 2), 1);
+
+        run(&mut processor, 3);
+        assert_eq!(reg(&processor, 0), 0x78);
+        assert_eq!(reg(&processor, 2), 1);
+    }
+
+    #[test]
+    fn exclusive_monitor_exception() {
+        // ldrex r0, [r1]; svc #0; strex r2, r3, [r1] with bx lr as the handler at 0x180
+        let mut processor = processor(&[0xe851, 0x0f00, 0xdf00, 0xe841, 0x3200]);
+
+        processor.write::<u16>(0x180, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::SVCall) * 4, 0x181);
+
+        set(&mut processor, 1, 0x20000000);
+
+        run(&mut processor, 4);
+        assert_eq!(processor.registers.pc(), 0x10a);
+        assert_eq!(reg(&processor, 2), 1);
+    }
+
+    #[test]
+    fn lazy_fp_stacking() {
+        // vmov.f32 s0, #1.0; svc #0 with vmov.f32 s0, #2.0; bx lr as the handler at 0x180
+        let mut processor = processor(&[0xeeb7, 0x0a00, 0xdf00]);
+
+        for (offset, halfword) in [0xeeb0, 0x0a00, 0x4770].iter().enumerate() {
+            processor.write::<u16>(0x180 + offset * 2, *halfword);
+        }
+
+        processor.write::<u32>(Into::<usize>::into(Exception::SVCall) * 4, 0x181);
+        processor.write::<u32>(0xe000ed88, 0x00f00000);
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 14), 0xffffffe9);
+        assert_eq!(reg(&processor, 13), 0x20000f98);
+        assert_eq!(processor.read::<u32>(0xe000ef34) & 0x1b, 0x19);
+        assert_eq!(processor.read::<u32>(0xe000ef38), 0x20000fb8);
+
+        run(&mut processor, 1);
+        assert!(!processor.registers.fpccr.lspact());
+        assert_eq!(f32::from_bits(processor.read::<u32>(0x20000fb8)), 1.0);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.mode, Mode::Thread);
+        assert_eq!(reg(&processor, 13), 0x20001000);
+        assert_eq!(f32::from_bits(processor.registers.fp[0]), 1.0);
+        assert!(processor.registers.control.fpca);
+    }
+
+    #[test]
+    fn eager_fp_stacking() {
+        // vmov.f32 s0, #1.0; svc #0 with bx lr as the handler at 0x180
+        let mut processor = processor(&[0xeeb7, 0x0a00, 0xdf00]);
+
+        processor.write::<u16>(0x180, 0x4770);
+        processor.write::<u32>(Into::<usize>::into(Exception::SVCall) * 4, 0x181);
+        processor.write::<u32>(0xe000ed88, 0x00f00000);
+        processor.write::<u32>(0xe000ef34, 0x80000000);
+
+        run(&mut processor, 2);
+        assert_eq!(reg(&processor, 14), 0xffffffe9);
+        assert!(!processor.registers.fpccr.lspact());
+        assert_eq!(f32::from_bits(processor.read::<u32>(0x20000fb8)), 1.0);
+
+        processor.registers.fp[0] = 0;
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 13), 0x20001000);
+        assert_eq!(f32::from_bits(processor.registers.fp[0]), 1.0);
+    }
+
+    #[test]
+    fn if_then_flags() {
+        // cmp r0, #1; ite eq; moveq r1, #1; movne r1, #2
+        let mut processor = processor(&[0x2801, 0xbf0c, 0x2101, 0x2102]);
+
+        set(&mut processor, 0, 1);
+
+        run(&mut processor, 4);
+        assert_eq!(reg(&processor, 1), 1);
+        assert_eq!(nzcv(&processor), (false, true, true, false));
+    }
+
+    #[test]
+    fn if_then_exception() {
+        // cmp r0, #1; itt eq; svceq #0; moveq r1, #1 with bx lr as the handler at 0x180
+        let mut processor = processor(&[0x2801, 0xbf04, 0xdf00, 0x2101]);
+
+        processor.write::<u32>(Into::<usize>::into(Exception::SVCall) * 4, 0x181);
+        processor.write::<u16>(0x180, 0x4770);
+
+        set(&mut processor, 0, 1);
+
+        run(&mut processor, 3);
+        assert_eq!(processor.registers.pc(), 0x180);
+        assert!(!processor.registers.psr.in_it_block());
+        assert_ne!(processor.read::<u32>(reg(&processor, 13) as usize + 0x1c) & 0x0600fc00, 0);
+
+        run(&mut processor, 1);
+        assert_eq!(processor.registers.pc(), 0x106);
+        assert_eq!(processor.registers.psr.it_condition(), instruction::Condition::Eq);
+
+        run(&mut processor, 1);
+        assert_eq!(reg(&processor, 1), 1);
+        assert!(!processor.registers.psr.in_it_block());
+    }
+
+    #[test]
+    fn if_then_skipped_branch() {
+        // cmp r0, #1; it ne; bne.w #-8; nop
+        let mut processor = processor(&[0x2801, 0xbf18, 0xf47f, 0xaffc, 0xbf00]);
+
+        set(&mut processor, 0, 1);
+
+        run(&mut processor, 3);
+        assert_eq!(processor.registers.pc(), 0x108);
+    }
+}
 
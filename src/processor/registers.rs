@@ -1,5 +1,6 @@
 use super::decoder::BitVec;
-use super::{Mode, RAM_CAPACITY};
+use super::instruction::Condition;
+use super::Mode;
 
 use crate::bus::BitSize;
 
@@ -9,6 +10,7 @@ use crate::bus::BitSize;
 pub struct Control {
     pub private: bool,
     pub stack: bool,
+    pub fpca: bool,
 }
 
 impl Control {
@@ -16,6 +18,7 @@ impl Control {
         Control {
             private,
             stack,
+            fpca: false,
         }
     }
 }
@@ -43,17 +46,28 @@ impl StackPointer {
     }
 
     pub fn get(&self, control: Control, mode: Mode) -> u32 {
-        (control.stack && mode == Mode::Thread).then(|| self.psp).unwrap_or(self.msp)
+        if control.stack && mode == Mode::Thread { self.psp } else { self.msp }
     }
 }
 
+// page 48 @ ARMv7M Reference Manual
+#[derive(Clone, Copy)]
+pub enum Flag {
+    N = 31,
+    Z = 30,
+    C = 29,
+    V = 28,
+    Q = 27,
+}
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone)]
 pub struct PSR {
     pub value: u32,
 }
 
 impl PSR {
-    pub fn new() -> PSR {
+    pub(crate) fn new() -> PSR {
         PSR {
             value: 0,
         }
@@ -70,8 +84,86 @@ impl PSR {
     pub fn unset(&mut self, bit: u32) {
         self.value &= !(1 << bit);
     }
+
+    pub fn write(&mut self, bit: u32, value: bool) {
+        if value {
+            self.set(bit);
+        } else {
+            self.unset(bit);
+        }
+    }
+
+    pub fn flag(&self, flag: Flag) -> bool {
+        self.get(flag as u32)
+    }
+
+    pub fn set_flag(&mut self, flag: Flag, value: bool) {
+        self.write(flag as u32, value);
+    }
+
+    // page 179 @ ARMv7M Reference Manual
+    pub fn passed(&self, cond: Condition) -> bool {
+        let (n, z, c, v) = (self.flag(Flag::N), self.flag(Flag::Z), self.flag(Flag::C), self.flag(Flag::V));
+
+        match cond {
+            Condition::Eq => z,
+            Condition::Ne => !z,
+            Condition::Cs => c,
+            Condition::Cc => !c,
+            Condition::Mi => n,
+            Condition::Pl => !n,
+            Condition::Vs => v,
+            Condition::Vc => !v,
+            Condition::Hi => c && !z,
+            Condition::Ls => !c || z,
+            Condition::Ge => n == v,
+            Condition::Lt => n != v,
+            Condition::Gt => !z && n == v,
+            Condition::Le => z || n != v,
+            Condition::Al => true,
+        }
+    }
+
+    // one bit per byte lane, written by the parallel add and subtract instructions
+    pub fn ge(&self) -> u8 {
+        (self.value.get(16..20) >> 16) as u8
+    }
+
+    pub fn set_ge(&mut self, ge: u8) {
+        self.value = (self.value & !(0xf << 16)) | ((ge as u32 & 0xf) << 16);
+    }
+
+    // ITSTATE is split between bits 15:10 and 26:25, page 50 @ ARMv7M Reference Manual
+    pub fn it_state(&self) -> u8 {
+        ((self.value.get(10..16) >> 8) | (self.value.get(25..27) >> 25)) as u8
+    }
+
+    pub fn set_it_state(&mut self, it: u8) {
+        self.value = (self.value & !0x0600fc00) | ((it as u32 & 0xfc) << 8) | ((it as u32 & 0x3) << 25);
+    }
+
+    pub fn in_it_block(&self) -> bool {
+        self.it_state() & 0xf != 0
+    }
+
+    pub fn it_condition(&self) -> Condition {
+        match self.in_it_block() {
+            true => Condition::from(self.it_state() >> 4),
+            false => Condition::Al,
+        }
+    }
+
+    pub fn it_advance(&mut self) {
+        let it = self.it_state();
+
+        match it & 0x7 {
+            0 => self.set_it_state(0),
+            _ => self.set_it_state((it & 0xe0) | ((it << 1) & 0x1f)),
+        }
+    }
 }
 
+#[allow(dead_code)]
 pub enum TableBase {
     Code,
     Ram,
@@ -100,39 +192,187 @@ impl Vtor {
 
     pub fn read<T>(&self) -> T where T: BitSize { T::from(&self.value.to_bytes()) }
 
-    pub fn write<T>(&mut self, value: T) where T: BitSize + Into<u32> { self.value = value.into() }
+    pub fn write<T>(&mut self, value: T) where T: BitSize + Into<u32> { self.value = value.into() & 0x3fffff80 }
 
+    // tbloff already holds the table address, bit 29 selects the sram region
     pub fn addr(&self) -> u32 {
-        match self.value & (1 << 29) {
-            0 => self.value.get(7..29) >> 7,
-            _ => (self.value.get(7..29) >> 7) + 0x20000000,
+        self.value.get(7..30)
+    }
+}
+
+// page 613 @ ARMv7M Reference Manual
+#[derive(Clone)]
+pub struct Cpacr {
+    value: u32,
+}
+
+impl Cpacr {
+    pub(crate) fn new() -> Cpacr {
+        Cpacr {
+            value: 0,
+        }
+    }
+
+    pub fn read<T>(&self) -> T where T: BitSize { T::from(&self.value.to_bytes()) }
+
+    pub fn write<T>(&mut self, value: T) where T: BitSize + Into<u32> { self.value = value.into() & 0x00f00000 }
+
+    // cp10 and cp11 must be programmed the same, so cp10 decides access to the fpu
+    pub fn enabled(&self, privileged: bool) -> bool {
+        match self.value.get(20..22) >> 20 {
+            0b01 => privileged,
+            0b11 => true,
+            _ => false,
         }
     }
 }
 
+// page 614 @ ARMv7M Reference Manual
+#[derive(Clone)]
+pub struct Fpccr {
+    value: u32,
+}
+
+impl Fpccr {
+    pub(crate) fn new() -> Fpccr {
+        Fpccr {
+            value: 0xc0000000,
+        }
+    }
+
+    pub fn read<T>(&self) -> T where T: BitSize { T::from(&self.value.to_bytes()) }
+
+    pub fn write<T>(&mut self, value: T) where T: BitSize + Into<u32> { self.value = value.into() & 0xc000017b }
+
+    pub fn aspen(&self) -> bool {
+        self.value & (1 << 31) != 0
+    }
+
+    pub fn lspen(&self) -> bool {
+        self.value & (1 << 30) != 0
+    }
+
+    pub fn lspact(&self) -> bool {
+        self.value & 1 != 0
+    }
+
+    pub fn set_lspact(&mut self, active: bool) {
+        self.value = (self.value & !1) | active as u32;
+    }
+
+    // records the context that deferred its floating point state, HFRDY is always set as nothing can mask a hardfault here
+    pub fn defer(&mut self, user: bool, thread: bool) {
+        self.value = (self.value & !0x1b) | (1 << 4) | ((thread as u32) << 3) | ((user as u32) << 1) | 1;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Rounding {
+    Nearest,
+    PlusInfinity,
+    MinusInfinity,
+    Zero,
+}
+
+// cumulative exception bits of the fpscr
+#[derive(Clone, Copy)]
+pub enum FpException {
+    InvalidOperation = 0,
+    DivideByZero = 1,
+    Overflow = 2,
+    Underflow = 3,
+    Inexact = 4,
+    InputDenormal = 7,
+}
+
+// page 78 @ ARMv7M Reference Manual
+#[derive(Clone)]
+pub struct Fpscr {
+    pub value: u32,
+}
+
+impl Fpscr {
+    pub(crate) fn new() -> Fpscr {
+        Fpscr {
+            value: 0,
+        }
+    }
+
+    pub fn flag(&self, flag: Flag) -> bool {
+        self.value & (1 << flag as u32) != 0
+    }
+
+    pub fn set_nzcv(&mut self, nzcv: u32) {
+        self.value = (self.value & 0x0fffffff) | (nzcv << 28);
+    }
+
+    pub fn raise(&mut self, exception: FpException) {
+        self.value |= 1 << exception as u32;
+    }
+
+    pub fn rounding(&self) -> Rounding {
+        match self.value.get(22..24) >> 22 {
+            0b00 => Rounding::Nearest,
+            0b01 => Rounding::PlusInfinity,
+            0b10 => Rounding::MinusInfinity,
+            _ => Rounding::Zero,
+        }
+    }
+
+    pub fn flush_to_zero(&self) -> bool {
+        self.value & (1 << 24) != 0
+    }
+
+    pub fn default_nan(&self) -> bool {
+        self.value & (1 << 25) != 0
+    }
+
+    pub fn alternative_half(&self) -> bool {
+        self.value & (1 << 26) != 0
+    }
+}
+
 #[derive(Clone)]
 pub struct Registers {
     registers: [u32; 16],
     pub vtor: Vtor,
+    pub cpacr: Cpacr,
+    pub fp: [u32; 32],
+    pub fpscr: Fpscr,
+    pub fpccr: Fpccr,
+    pub fpcar: u32,
+    pub fpdscr: u32,
     pub psr: PSR,
     pub sp: StackPointer,
     pub control: Control,
+    pub primask: bool,
+    pub faultmask: bool,
+    pub basepri: u8,
 }
 
 impl Registers {
-    pub fn new() -> Registers {
+    pub(crate) fn new() -> Registers {
         Registers {
             registers: [0; 16],
             vtor: Vtor::new(TableBase::Code, 0),
+            cpacr: Cpacr::new(),
+            fp: [0; 32],
+            fpscr: Fpscr::new(),
+            fpccr: Fpccr::new(),
+            fpcar: 0,
+            fpdscr: 0,
             psr: PSR::new(),
-            sp: StackPointer::new((0x20000000 + RAM_CAPACITY) as u32),
+            sp: StackPointer::new(0),
             control: Control::new(false, false),
+            primask: false,
+            faultmask: false,
+            basepri: 0,
         }
     }
 
     pub fn set<F>(&mut self, register: u8, f: F, mode: Mode) where F: Fn(u32) -> u32 {
         match register {
-            13 => self.sp.set(self.control, mode, |value| f(value)),
+            13 => self.sp.set(self.control, mode, &f),
             15 => self.registers[register as usize] = f(self.registers[register as usize]) & 0xfffffffe,
             _ => self.registers[register as usize] = f(self.registers[register as usize]),
         }
@@ -141,12 +381,78 @@ impl Registers {
     pub fn get(&self, register: u8, mode: Mode) -> u32 {
         match register {
             13 => self.sp.get(self.control, mode),
-            15 => self.registers[register as usize] + 4,
+            15 => self.registers[register as usize].wrapping_add(4),
             _ => self.registers[register as usize],
         }
     }
 
     pub fn all(&self) -> [u32; 16] { self.registers }
+
+    pub fn pc(&self) -> u32 { self.registers[15] }
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn psr(n: bool, z: bool, c: bool, v: bool) -> PSR {
+        let mut psr = PSR::new();
+
+        psr.set_flag(Flag::N, n);
+        psr.set_flag(Flag::Z, z);
+        psr.set_flag(Flag::C, c);
+        psr.set_flag(Flag::V, v);
+
+        psr
+    }
+
+    #[test]
+    fn condition_codes() {
+        let cases = [
+            (Condition::Eq, psr(false, true, false, false), psr(false, false, false, false)),
+            (Condition::Ne, psr(false, false, false, false), psr(false, true, false, false)),
+            (Condition::Cs, psr(false, false, true, false), psr(false, false, false, false)),
+            (Condition::Cc, psr(false, false, false, false), psr(false, false, true, false)),
+            (Condition::Mi, psr(true, false, false, false), psr(false, false, false, false)),
+            (Condition::Pl, psr(false, false, false, false), psr(true, false, false, false)),
+            (Condition::Vs, psr(false, false, false, true), psr(false, false, false, false)),
+            (Condition::Vc, psr(false, false, false, false), psr(false, false, false, true)),
+            (Condition::Hi, psr(false, false, true, false), psr(false, true, true, false)),
+            (Condition::Ls, psr(false, true, true, false), psr(false, false, true, false)),
+            (Condition::Ge, psr(true, false, false, true), psr(true, false, false, false)),
+            (Condition::Lt, psr(true, false, false, false), psr(true, false, false, true)),
+            (Condition::Gt, psr(false, false, false, false), psr(false, true, false, false)),
+            (Condition::Le, psr(false, true, false, false), psr(false, false, false, false)),
+        ];
+
+        for (cond, pass, fail) in cases {
+            assert!(pass.passed(cond), "{:?} should pass", cond);
+            assert!(!fail.passed(cond), "{:?} should fail", cond);
+        }
+
+        assert!(PSR::new().passed(Condition::Al));
+    }
+
+    #[test]
+    fn it_state() {
+        let mut psr = PSR::new();
+
+        // itet lt
+        psr.set_it_state(0b1011_0110);
+
+        assert_eq!(psr.value, (0b101101 << 10) | (0b10 << 25));
+        assert_eq!(psr.it_condition(), Condition::Lt);
+
+        psr.it_advance();
+        assert_eq!(psr.it_condition(), Condition::Ge);
+
+        psr.it_advance();
+        assert_eq!(psr.it_condition(), Condition::Lt);
+
+        psr.it_advance();
+        assert!(!psr.in_it_block());
+        assert_eq!(psr.value, 0);
+    }
+}
@@ -0,0 +1,183 @@
+use super::PRIORITY_MASK;
+use super::fault::{Exception, Fault};
+
+use crate::bus::BitSize;
+
+
+// page 611 @ ARMv7M Reference Manual
+#[derive(Clone)]
+pub struct Ccr {
+    value: u32,
+}
+
+impl Ccr {
+    pub fn new() -> Ccr {
+        Ccr {
+            value: 1 << 9,
+        }
+    }
+
+    pub fn read<T>(&self) -> T where T: BitSize { T::from(&self.value.to_bytes()) }
+
+    pub fn write<T>(&mut self, value: T) where T: BitSize + Into<u32> { self.value = value.into() & 0x31b }
+
+    pub fn nonbasethrdena(&self) -> bool {
+        self.value & 1 != 0
+    }
+
+    pub fn unalign_trp(&self) -> bool {
+        self.value & (1 << 3) != 0
+    }
+
+    pub fn div_0_trp(&self) -> bool {
+        self.value & (1 << 4) != 0
+    }
+
+    pub fn stkalign(&self) -> bool {
+        self.value & (1 << 9) != 0
+    }
+}
+
+// page 604 @ ARMv7M Reference Manual
+#[derive(Clone)]
+pub struct Scb {
+    prigroup: u8,
+    scr: u32,
+    shcsr: u32,
+    mmfar: u32,
+    bfar: u32,
+    afsr: u32,
+    pub ccr: Ccr,
+    pub cfsr: u32,
+    pub hfsr: u32,
+    pub dfsr: u32,
+}
+
+impl Scb {
+    pub fn new() -> Scb {
+        Scb {
+            prigroup: 0,
+            scr: 0,
+            shcsr: 0,
+            mmfar: 0,
+            bfar: 0,
+            afsr: 0,
+            ccr: Ccr::new(),
+            cfsr: 0,
+            hfsr: 0,
+            dfsr: 0,
+        }
+    }
+
+    // page 526 @ ARMv7M Reference Manual
+    pub fn group(&self, priority: i32) -> i32 {
+        match priority {
+            0.. => priority & ((0xff << (self.prigroup + 1)) & PRIORITY_MASK as i32),
+            _ => priority,
+        }
+    }
+
+    // page 609 @ ARMv7M Reference Manual
+    pub fn enabled(&self, exception: Exception) -> bool {
+        match exception {
+            Exception::MemManage => self.shcsr & (1 << 16) != 0,
+            Exception::BusFault => self.shcsr & (1 << 17) != 0,
+            Exception::UsageFault => self.shcsr & (1 << 18) != 0,
+            _ => true,
+        }
+    }
+
+    pub fn sleep_on_exit(&self) -> bool {
+        self.scr & (1 << 1) != 0
+    }
+
+    pub fn sev_on_pend(&self) -> bool {
+        self.scr & (1 << 4) != 0
+    }
+
+    pub fn record(&mut self, fault: Fault) {
+        self.cfsr |= fault.status();
+
+        if let Fault::PreciseData { addr } = fault {
+            self.bfar = addr;
+            self.cfsr |= 1 << 15;
+        }
+    }
+
+    // the offset is relative to 0xe000ed00, returns whether a system reset was requested
+    pub fn write_register(&mut self, offset: usize, value: u32, mask: u32) -> bool {
+        let masked = |old: u32| (old & !mask) | (value & mask);
+
+        match offset {
+            0x0c if masked(0) >> 16 == 0x05fa => {
+                self.prigroup = ((masked((self.prigroup as u32) << 8) >> 8) & 0b111) as u8;
+
+                return value & mask & (1 << 2) != 0;
+            },
+            0x10 => self.scr = masked(self.scr) & 0x16,
+            0x14 => self.ccr.write(masked(self.ccr.read())),
+            0x24 => self.shcsr = masked(self.shcsr) & 0x70000,
+            0x28 => self.cfsr &= !(value & mask),
+            0x2c => self.hfsr &= !(value & mask),
+            0x30 => self.dfsr &= !(value & mask),
+            0x34 => self.mmfar = masked(self.mmfar),
+            0x38 => self.bfar = masked(self.bfar),
+            0x3c => self.afsr = masked(self.afsr),
+            _ => {},
+        }
+
+        false
+    }
+
+    pub fn read_register(&self, offset: usize) -> u32 {
+        match offset {
+            0x0c => 0xfa050000 | (self.prigroup as u32) << 8,
+            0x10 => self.scr,
+            0x14 => self.ccr.read(),
+            0x24 => self.shcsr,
+            0x28 => self.cfsr,
+            0x2c => self.hfsr,
+            0x30 => self.dfsr,
+            0x34 => self.mmfar,
+            0x38 => self.bfar,
+            0x3c => self.afsr,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn priority_grouping() {
+        let mut scb = Scb::new();
+
+        assert_eq!(scb.group(0xe0), 0xe0);
+        assert_eq!(scb.group(-1), -1);
+
+        assert!(!scb.write_register(0x0c, 0x05fa0500, u32::MAX));
+        assert_eq!(scb.read_register(0x0c), 0xfa050500);
+        assert_eq!(scb.group(0xe0), 0xc0);
+
+        scb.write_register(0x0c, 0x00000700, u32::MAX);
+        assert_eq!(scb.read_register(0x0c), 0xfa050500);
+
+        // a halfword write of the key alone leaves prigroup alone
+        scb.write_register(0x0c, 0x05fa0000, 0xffff0000);
+        assert_eq!(scb.read_register(0x0c), 0xfa050500);
+
+        assert!(scb.write_register(0x0c, 0x05fa0704, u32::MAX));
+        assert_eq!(scb.group(0xe0), 0);
+    }
+
+    #[test]
+    fn system_control() {
+        let mut scb = Scb::new();
+
+        scb.write_register(0x10, 0xff, u32::MAX);
+        assert_eq!(scb.read_register(0x10), 0x16);
+        assert!(scb.sleep_on_exit() && scb.sev_on_pend());
+    }
+}
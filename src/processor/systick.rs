@@ -0,0 +1,104 @@
+// page 621 @ ARMv7M Reference Manual
+#[derive(Clone)]
+pub struct SysTick {
+    csr: u32,
+    rvr: u32,
+    cvr: u32,
+}
+
+impl SysTick {
+    pub fn new() -> SysTick {
+        SysTick {
+            csr: 1 << 2,
+            rvr: 0,
+            cvr: 0,
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.csr & 1 != 0
+    }
+
+    // counts down by the given number of processor cycles, returns whether systick should be pended
+    pub fn tick(&mut self, cycles: u64) -> bool {
+        let mut pend = false;
+
+        for _ in 0..cycles {
+            if !self.enabled() {
+                break;
+            }
+
+            match self.cvr {
+                0 => self.cvr = self.rvr,
+                1 => {
+                    self.cvr = 0;
+                    self.csr |= 1 << 16;
+
+                    pend |= self.csr & (1 << 1) != 0;
+                },
+                _ => self.cvr -= 1,
+            }
+        }
+
+        pend
+    }
+
+    // the offset is relative to 0xe000e010 and word aligned
+    pub fn read_register(&mut self, offset: usize) -> u32 {
+        match offset {
+            0x0 => {
+                let csr = self.csr;
+
+                self.csr &= !(1 << 16);
+
+                csr
+            },
+            0x4 => self.rvr,
+            0x8 => self.cvr,
+            // there is no reference clock and the calibration value is unknown
+            _ => 0xc0000000,
+        }
+    }
+
+    // only the bits in mask are written, the rest of the register keeps its value
+    pub fn write_register(&mut self, offset: usize, value: u32, mask: u32) {
+        match offset {
+            // the processor clock is the only clock source
+            0x0 => self.csr = (self.csr & (1 << 16)) | (((self.csr & !mask) | (value & mask)) & 0b011) | (1 << 2),
+            0x4 => self.rvr = ((self.rvr & !mask) | (value & mask)) & 0x00ffffff,
+            0x8 => {
+                self.cvr = 0;
+                self.csr &= !(1 << 16);
+            },
+            _ => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn countdown() {
+        let mut systick = SysTick::new();
+
+        systick.write_register(0x4, 2, u32::MAX);
+        assert!(!systick.tick(4));
+
+        systick.write_register(0x0, 0b011, u32::MAX);
+        assert!(!systick.tick(1));
+        assert_eq!(systick.read_register(0x8), 2);
+
+        assert!(systick.tick(2));
+        assert_eq!(systick.read_register(0x0), 0x10007);
+        assert_eq!(systick.read_register(0x0), 0x7);
+
+        assert!(!systick.tick(2));
+        assert!(systick.tick(1));
+
+        systick.write_register(0x8, 0x1234, u32::MAX);
+        assert_eq!(systick.read_register(0x8), 0);
+        assert_eq!(systick.read_register(0xc), 0xc0000000);
+    }
+}
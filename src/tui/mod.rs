@@ -1,6 +1,6 @@
 mod widgets;
 
-use crate::processor::Processor;
+use cortex_m4::processor::{Processor, Stop};
 
 use ratatui::prelude::*;
 use crossterm::{terminal, event::{self, *}, ExecutableCommand};
@@ -36,18 +36,20 @@ pub struct Tui {
     terminal: Terminal<CrosstermBackend<io::Stdout>>,
     processor: Processor,
     step: Step,
+    stop: Option<Stop>,
     should_close: bool,
 }
 
 impl Tui {
-    pub fn new() -> Result<Tui, Box<dyn std::error::Error>> {
+    pub fn new(processor: Processor) -> Result<Tui, Box<dyn std::error::Error>> {
         terminal::enable_raw_mode()?;
         io::stdout().execute(terminal::EnterAlternateScreen)?;
 
         Ok(Tui {
             terminal: Terminal::new(CrosstermBackend::new(io::stdout()))?,
-            processor: Processor::new(),
+            processor,
             step: Step::Never,
+            stop: None,
             should_close: false,
         })
     }
@@ -64,16 +66,10 @@ impl Tui {
     }
 
     fn handle_event(&mut self, event: Event) -> Result<(), Box<dyn std::error::Error>> {
-        match event {
-            Event::Key(event) => {
-                match event.kind {
-                    KeyEventKind::Press => {
-                        self.handle_keypress(event.code)?;
-                    },
-                    _ => {},
-                }
-            },
-            _ => {},
+        if let Event::Key(event) = event {
+            if event.kind == KeyEventKind::Press {
+                self.handle_keypress(event.code)?;
+            }
         }
 
         Ok(())
@@ -87,24 +83,24 @@ impl Tui {
         Ok(())
     }
 
-    pub fn flash(&mut self, rom: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
-        self.processor.flash(rom)
-    }
-
     pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.processor.reset();
 
         while !self.should_close {
             self.poll_event()?;
 
-            let processor = self.processor.clone();
+            let (processor, stop) = (self.processor.clone(), self.stop);
 
             self.terminal.draw(move |frame| {
-                widgets::draw(frame, processor);
+                widgets::draw(frame, processor, stop);
             })?;
 
             if self.step.should_step() {
-                self.processor.step();
+                self.stop = self.processor.step();
+
+                if self.stop.is_some() {
+                    self.step = Step::Never;
+                }
             }
         }
 
@@ -1,8 +1,8 @@
 use super::Processor;
 
-use crate::processor::instruction::{Instruction, InstructionKind};
-use crate::processor::registers::Registers;
-use crate::processor::Mode;
+use cortex_m4::processor::instruction::{Condition, Instruction, InstructionKind};
+use cortex_m4::processor::registers::Registers;
+use cortex_m4::processor::{Mode, Stop};
 
 use ratatui::style::palette::tailwind;
 use ratatui::{prelude::*, widgets::*};
@@ -25,26 +25,34 @@ pub struct Widgets<'a, 'b> {
     frame: &'b mut Frame<'a>,
     registers: RegisterWidget,
     instruction: InstructionWidget,
+    stop: Option<Stop>,
+    lockup: bool,
 }
 
 impl<'a, 'b> Widgets<'a, 'b> {
-    pub fn new(frame: &'b mut Frame<'a>, mut processor: Processor) -> Widgets<'a, 'b> {
+    pub fn new(frame: &'b mut Frame<'a>, mut processor: Processor, stop: Option<Stop>) -> Widgets<'a, 'b> {
         let registers = processor.registers.clone();
+        let lockup = processor.locked_up();
 
-        let mut instructions = vec![Instruction { kind: InstructionKind::Undefined, addr: 0, size: 2, }; 16];
+        let mut instructions = vec![Instruction { kind: InstructionKind::Undefined, condition: Condition::Al, addr: 0, size: 2, }; 16];
 
-        instructions.fill_with(|| {
-            let inst = processor.fetch();
+        // the lockup address is never fetched from, there is nothing to disassemble
+        if !lockup {
+            instructions.fill_with(|| {
+                let inst = processor.fetch();
 
-            processor.registers.set(15, |pc| pc + 2, processor.mode);
+                processor.skip(&inst);
 
-            inst
-        });
+                inst
+            });
+        }
 
         Widgets {
             frame,
             registers: RegisterWidget::new(registers.clone(), processor.mode),
             instruction: InstructionWidget::new(instructions),
+            stop,
+            lockup,
         }
     }
 
@@ -56,15 +64,22 @@ impl<'a, 'b> Widgets<'a, 'b> {
     }
 
     fn border(&mut self, area: Rect) {
-        let block = Block::bordered()
-            .title("cortex-m4");
+        let block = match self.lockup {
+            true => Block::bordered().title("cortex-m4 (lockup)").fg(tailwind::RED.c400),
+            false => Block::bordered().title("cortex-m4"),
+        };
 
         self.frame.render_widget(block, area);
     }
 
     fn footer(&mut self, area: Rect) {
-        let paragraph = Paragraph::new(Line::from(INFO))
-            .fg(tailwind::SLATE.c200)
+        let (info, color) = match self.stop {
+            Some(stop) => (format!("stopped: {}", stop), tailwind::RED.c400),
+            None => (String::from(INFO), tailwind::SLATE.c200),
+        };
+
+        let paragraph = Paragraph::new(Line::from(info))
+            .fg(color)
             .bg(tailwind::SLATE.c950)
             .centered()
             .block(
@@ -120,12 +135,12 @@ impl RegisterWidget {
             .map(|(register, value)| {
                 let row = match register {
                     13 => Row::new([
-                        format!("sp"),
+                        String::from("sp"),
                         format!("{}", self.registers.sp.get(self.registers.control, self.mode)),
                         String::from("Stack Pointer")
                     ]),
-                    14 => Row::new([format!("lr"), format!("{}", value), String::from("Link Register")]),
-                    15 => Row::new([format!("pc"), format!("{}", value), String::from("Program Counter")]),
+                    14 => Row::new([String::from("lr"), format!("{}", value), String::from("Link Register")]),
+                    15 => Row::new([String::from("pc"), format!("{}", value), String::from("Program Counter")]),
                     _ => Row::new([format!("r{}", register), format!("{}", value), String::from("Generic")]),
                 };
 
@@ -167,7 +182,7 @@ impl InstructionWidget {
         let rows = self.instructions.iter()
             .enumerate()
             .map(|(count, inst)| {
-                Row::new([format!("{:#x?}", inst.addr), format!("{}", inst.kind)])
+                Row::new([format!("{:#x?}", inst.addr), format!("{}", inst)])
                     .fg(tailwind::SLATE.c200).bg(alternate!(count))
             })
             .collect::<Vec<Row>>();
@@ -190,8 +205,8 @@ impl InstructionWidget {
     }
 }
 
-pub fn draw<'a, 'b>(frame: &'b mut Frame<'a>, processor: Processor) {
-    let mut widgets = Widgets::new(frame, processor);
+pub fn draw<'a, 'b>(frame: &'b mut Frame<'a>, processor: Processor, stop: Option<Stop>) {
+    let mut widgets = Widgets::new(frame, processor, stop);
 
     widgets.draw();
 }